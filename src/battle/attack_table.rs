@@ -0,0 +1,70 @@
+use crate::drivers::LockResult;
+
+
+// garbage sent for clearing 0 to 4 lines, normally and with a T spin
+const LINE_CLEAR_ATTACK: [usize; 5] = [0, 0, 1, 2, 4];
+const T_SPIN_ATTACK: [usize; 5] = [0, 2, 4, 6, 6];
+// extra garbage for each consecutive placement that clears lines, after the first
+const COMBO_ATTACK: &[usize] = &[0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const BACK_TO_BACK_BONUS: usize = 1;
+const PERFECT_CLEAR_BONUS: usize = 10;
+
+/**
+ * Turns each of a player's placements into the number of garbage lines they send.  Combos and
+ * back to back clears depend on the placements before them, so every player needs their own.
+ */
+#[derive(Clone)]
+pub struct AttackTable {
+    // the number of consecutive placements that cleared lines, minus one
+    combo: Option<usize>,
+    // whether the last clear was a tetris or a T spin
+    back_to_back: bool,
+}
+
+impl AttackTable {
+    pub fn new() -> Self {
+        Self {
+            combo: None,
+            back_to_back: false,
+        }
+    }
+
+    pub fn get_combo(&self) -> Option<usize> {
+        self.combo
+    }
+
+    pub fn is_back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    pub fn calculate_attack(&mut self, lock_result: &LockResult) -> usize {
+        if lock_result.lines_cleared == 0 {
+            self.combo = None;
+            return 0
+        }
+
+        let lines = std::cmp::min(lock_result.lines_cleared, LINE_CLEAR_ATTACK.len() - 1);
+        let mut attack = if lock_result.t_spin {
+            T_SPIN_ATTACK[lines]
+        } else {
+            LINE_CLEAR_ATTACK[lines]
+        };
+
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        attack += COMBO_ATTACK[std::cmp::min(combo, COMBO_ATTACK.len() - 1)];
+        self.combo = Some(combo);
+
+        // only a different kind of clear breaks a back to back chain, placing without clearing does not
+        let difficult = lines >= 4 || lock_result.t_spin;
+        if difficult && self.back_to_back {
+            attack += BACK_TO_BACK_BONUS;
+        }
+        self.back_to_back = difficult;
+
+        if lock_result.perfect_clear {
+            attack += PERFECT_CLEAR_BONUS;
+        }
+
+        attack
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+
+
+/**
+ * The garbage waiting to rise beneath a player's stack.  Each attack waits for a delay before
+ * it can rise, giving the player a chance to cancel it out by sending garbage of their own.
+ */
+#[derive(Clone)]
+pub struct GarbageQueue {
+    // each attack, oldest first, with the number of lines and the frames until it can rise
+    attacks: VecDeque<(usize, usize)>,
+    delay: usize,
+}
+
+impl GarbageQueue {
+    pub fn new(delay: usize) -> Self {
+        Self {
+            attacks: VecDeque::new(),
+            delay,
+        }
+    }
+
+    pub fn push(&mut self, lines: usize) {
+        if lines > 0 {
+            self.attacks.push_back((lines, self.delay));
+        }
+    }
+
+    /**
+     * Cancels out the oldest garbage first, returning the lines that were left over.
+     */
+    pub fn cancel(&mut self, mut lines: usize) -> usize {
+        while lines > 0 {
+            match self.attacks.front_mut() {
+                Some((attack, _)) if *attack > lines => {
+                    *attack -= lines;
+                    lines = 0;
+                },
+                Some((attack, _)) => {
+                    lines -= *attack;
+                    self.attacks.pop_front();
+                },
+                None => break
+            }
+        }
+
+        lines
+    }
+
+    pub fn next_frame(&mut self) {
+        for (_, frames) in self.attacks.iter_mut() {
+            *frames = frames.saturating_sub(1);
+        }
+    }
+
+    /**
+     * Takes up to the given number of lines from the attacks that are ready to rise, returning
+     * the number of lines taken from each attack.
+     */
+    pub fn take_ready(&mut self, max_lines: usize) -> Vec<usize> {
+        let mut taken = Vec::new();
+        let mut remaining = max_lines;
+        while remaining > 0 {
+            match self.attacks.front_mut() {
+                Some((attack, 0)) => {
+                    let lines = std::cmp::min(*attack, remaining);
+                    *attack -= lines;
+                    remaining -= lines;
+                    taken.push(lines);
+
+                    if *attack == 0 {
+                        self.attacks.pop_front();
+                    }
+                },
+                _ => break
+            }
+        }
+
+        taken
+    }
+
+    pub fn get_pending_lines(&self) -> usize {
+        self.attacks.iter().map(|(lines, _)| lines).sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.attacks.clear();
+    }
+}
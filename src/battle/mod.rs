@@ -0,0 +1,211 @@
+pub mod attack_table;
+pub mod garbage_queue;
+pub mod targeting;
+
+use rand::Rng;
+
+use crate::drivers::*;
+use crate::drivers::utils::tetrimino_chooser::{rng_from_seed, SeededRng};
+use crate::game_core::defaults::settings::FRAMES_PER_SECOND;
+use attack_table::AttackTable;
+use garbage_queue::GarbageQueue;
+
+
+// garbage waits a second before it can rise, and at most this many rows rise after a single placement
+pub const GARBAGE_DELAY: usize = FRAMES_PER_SECOND;
+pub const MAX_GARBAGE_PER_PLACEMENT: usize = 8;
+
+/**
+ * Pushes garbage in beneath the driver's stack, as the lines from each attack with one hole
+ * shared by all of the rows from the same attack.  A stack pushed over the top ends the game.
+ * Every game played against other players raises its garbage this way, whether the battle is
+ * run locally or by the match server.
+ */
+pub fn raise_garbage(driver: &mut dyn Driver, attacks: Vec<usize>, rng: &mut impl Rng) -> BoardTransition {
+    let width = driver.get_game_core().get_board().get_width() as i32;
+    let mut rows = Vec::new();
+    for lines in attacks {
+        let hole = rng.gen_range(0..width);
+        rows.extend((0..lines).map(|_| vec![hole]));
+    }
+
+    let mut transition = BoardTransition::new();
+    if rows.is_empty() {
+        return transition
+    }
+
+    if driver.get_game_core_mut().raise_garbage_rows(&rows) {
+        transition.add_rows_risen(rows.len());
+    } else {
+        driver.get_driver_core_mut().end_game(GameResult::ToppedOut);
+    }
+
+    transition
+}
+
+#[derive(Clone)]
+struct BattlePlayer {
+    attack_table: AttackTable,
+    garbage_queue: GarbageQueue,
+    // chooses the holes in the garbage this player receives
+    rng: SeededRng,
+    // ready garbage rises once a piece is placed without clearing any lines
+    garbage_ready: bool,
+    lines_sent: usize,
+    eliminated: bool,
+}
+
+/**
+ * The battle logic shared by every game played against other players, independent of how
+ * those games are controlled or displayed.  Each player's driver is passed in by index,
+ * and each player's transitions must be run through the battle instead of the driver.
+ */
+#[derive(Clone)]
+pub struct Battle {
+    players: Vec<BattlePlayer>,
+}
+
+impl Battle {
+    /**
+     * Every player uses the same seed, so that their drivers should be built with it as well.
+     */
+    pub fn new(num_players: usize, rng_seed: &[u8]) -> Self {
+        let players = (0..num_players)
+            .map(|i| {
+                let mut seed = rng_seed.to_vec();
+                seed.resize(32, 0);
+                seed[0] = seed[0].wrapping_add(i as u8);
+
+                BattlePlayer {
+                    attack_table: AttackTable::new(),
+                    garbage_queue: GarbageQueue::new(GARBAGE_DELAY),
+                    rng: rng_from_seed(&seed),
+                    garbage_ready: false,
+                    lines_sent: 0,
+                    eliminated: false,
+                }
+            })
+            .collect();
+
+        Self {
+            players,
+        }
+    }
+
+    pub fn get_lines_sent(&self, player: usize) -> usize {
+        self.players[player].lines_sent
+    }
+
+    pub fn get_garbage_pending(&self, player: usize) -> usize {
+        self.players[player].garbage_queue.get_pending_lines()
+    }
+
+    /**
+     * Lines describing the player's attacks, to display alongside their board.
+     */
+    pub fn get_status(&self, player: usize) -> Vec<String> {
+        let battle_player = &self.players[player];
+        let mut status = vec![format!("sent: {}", battle_player.lines_sent)];
+        if let Some(combo) = battle_player.attack_table.get_combo().filter(|&combo| combo > 0) {
+            status.push(format!("{} combo", combo));
+        }
+        if battle_player.attack_table.is_back_to_back() {
+            status.push("back to back".to_string());
+        }
+
+        status
+    }
+
+    /**
+     * The players left standing, once all but one of them have been eliminated.
+     */
+    pub fn get_standing_players(&self) -> Vec<usize> {
+        (0..self.players.len()).filter(|&i| !self.players[i].eliminated).collect()
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.get_standing_players().len() <= 1
+    }
+
+    /**
+     * The players that an attack from the given player is sent to.
+     */
+    fn get_targets(&self, attacker: usize) -> Vec<usize> {
+        (0..self.players.len())
+            .filter(|&i| i != attacker && !self.players[i].eliminated)
+            .collect()
+    }
+
+    /**
+     * Sends the attack to every target, after cancelling out the attacker's own pending garbage.
+     */
+    fn send_attack(&mut self, attacker: usize, lines: usize) {
+        let lines = self.players[attacker].garbage_queue.cancel(lines);
+        if lines == 0 {
+            return
+        }
+
+        self.players[attacker].lines_sent += lines;
+        for target in self.get_targets(attacker) {
+            self.players[target].garbage_queue.push(lines);
+        }
+    }
+
+
+    fn update_elimination(&mut self, player: usize, driver: &dyn Driver) {
+        if driver.is_game_over() && !self.players[player].eliminated {
+            self.players[player].eliminated = true;
+            self.players[player].garbage_queue.clear();
+        }
+    }
+
+    /**
+     * Replaces `Driver::next_frame`.  Garbage rises while the board is settled, in place of the
+     * player's next frame.
+     */
+    pub fn next_frame(&mut self, player: usize, driver: &mut dyn Driver) -> BoardTransition {
+        self.update_elimination(player, driver);
+        if self.players[player].eliminated {
+            return driver.next_frame()
+        }
+
+        self.players[player].garbage_queue.next_frame();
+        let transition = if self.players[player].garbage_ready {
+            let battle_player = &mut self.players[player];
+            battle_player.garbage_ready = false;
+            let attacks = battle_player.garbage_queue.take_ready(MAX_GARBAGE_PER_PLACEMENT);
+            raise_garbage(driver, attacks, &mut battle_player.rng)
+        } else {
+            BoardTransition::new()
+        };
+
+        let transition = if transition.is_inert() && !driver.is_game_over() {
+            driver.next_frame()
+        } else {
+            transition
+        };
+
+        self.update_elimination(player, driver);
+        transition
+    }
+
+    /**
+     * Replaces `Driver::finish_transition`, turning the player's placements into attacks.  The
+     * driver must be collecting its lock results.
+     */
+    pub fn finish_transition(&mut self, player: usize, driver: &mut dyn Driver, transition: BoardTransition) -> BoardTransition {
+        let new_transition = driver.finish_transition(transition);
+
+        for lock_result in driver.get_driver_core_mut().take_lock_results() {
+            if lock_result.lines_cleared == 0 {
+                self.players[player].garbage_ready = true;
+            }
+
+            let attack = self.players[player].attack_table.calculate_attack(&lock_result);
+            self.send_attack(player, attack);
+        }
+
+        self.update_elimination(player, driver);
+        new_transition
+    }
+}
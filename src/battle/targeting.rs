@@ -0,0 +1,92 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+
+
+// the badge points needed for each level of the badge bonus, which adds a quarter to every attack
+const BADGE_LEVELS: [usize; 4] = [2, 6, 14, 30];
+
+/**
+ * How a player in a match against many others chooses who their attacks are sent to.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TargetingStrategy {
+    Random,
+    // everyone currently targeting the player
+    Attackers,
+    // whoever is closest to being knocked out
+    KOs,
+    // whoever has the most badges to take
+    Badges,
+}
+
+impl TargetingStrategy {
+    pub const ALL: [TargetingStrategy; 4] = [
+        TargetingStrategy::Random,
+        TargetingStrategy::Attackers,
+        TargetingStrategy::KOs,
+        TargetingStrategy::Badges,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            TargetingStrategy::Random => "random",
+            TargetingStrategy::Attackers => "attackers",
+            TargetingStrategy::KOs => "KOs",
+            TargetingStrategy::Badges => "badges",
+        }
+    }
+}
+
+/**
+ * What a strategy knows about an opponent that is still standing.
+ */
+pub struct TargetCandidate {
+    pub player: usize,
+    // whether the opponent is currently targeting the attacker
+    pub is_attacking: bool,
+    // the opponent's stack height plus the garbage waiting to rise beneath it
+    pub danger: usize,
+    pub badge_points: usize,
+}
+
+/**
+ * Chooses the players that an attack is sent to.  Strategies that single out one player pick
+ * between any that are tied at random.
+ */
+pub fn choose_targets(strategy: TargetingStrategy, candidates: &[TargetCandidate], rng: &mut impl Rng) -> Vec<usize> {
+    if candidates.is_empty() {
+        return Vec::new()
+    }
+
+    match strategy {
+        TargetingStrategy::Random => vec![candidates.choose(rng).unwrap().player],
+        TargetingStrategy::Attackers => {
+            let attackers = candidates.iter().filter(|c| c.is_attacking).map(|c| c.player).collect::<Vec<_>>();
+            if attackers.is_empty() {
+                choose_targets(TargetingStrategy::Random, candidates, rng)
+            } else {
+                attackers
+            }
+        },
+        TargetingStrategy::KOs => vec![choose_most(candidates, |c| c.danger, rng)],
+        TargetingStrategy::Badges => vec![choose_most(candidates, |c| c.badge_points, rng)],
+    }
+}
+
+fn choose_most(candidates: &[TargetCandidate], key: fn(&TargetCandidate) -> usize, rng: &mut impl Rng) -> usize {
+    let most = candidates.iter().map(key).max().unwrap();
+    let tied = candidates.iter().filter(|c| key(c) == most).collect::<Vec<_>>();
+    tied.choose(rng).unwrap().player
+}
+
+/**
+ * The number of badges shown for the given badge points.
+ */
+pub fn get_badge_level(badge_points: usize) -> usize {
+    BADGE_LEVELS.iter().filter(|&&level| badge_points >= level).count()
+}
+
+pub fn apply_badge_bonus(attack: usize, badge_points: usize) -> usize {
+    attack + attack * get_badge_level(badge_points) / 4
+}
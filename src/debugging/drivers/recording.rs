@@ -3,9 +3,11 @@ use std::io::prelude::*;
 use serde::{Serialize, Deserialize};
 
 use crate::drivers::*;
+use crate::game_core::utils::point::Point;
+use crate::debugging::invariants::report_invariant_violations;
 
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Action {
     TranslateLeft,
     TranslateRight,
@@ -14,6 +16,31 @@ pub enum Action {
     Hold,
     Fastfall,
     Fall,
+    ActivateZone,
+    StartFastfalling,
+    StopFastfalling,
+}
+
+impl Action {
+    /**
+     * Performs the action on the driver, returning the transition it caused.
+     */
+    pub fn apply(self, driver: &mut dyn Driver) -> BoardTransition {
+        match self {
+            Action::TranslateLeft => driver.translate_left(),
+            Action::TranslateRight => driver.translate_right(),
+            Action::RotateClockwise => driver.rotate_clockwise(),
+            Action::RotateCounterClockwise => driver.rotate_counterclockwise(),
+            Action::Hold => driver.hold(),
+            Action::ActivateZone => driver.activate_zone(),
+            Action::StartFastfalling => driver.start_fastfalling(),
+            Action::StopFastfalling => driver.stop_fastfalling(),
+            Action::Fastfall => return driver.fastfall(),
+            Action::Fall => return driver.fall(),
+        }
+
+        BoardTransition::new()
+    }
 }
 
 pub struct RecordingDriver<'a> {
@@ -60,6 +87,30 @@ impl<'a> Driver for RecordingDriver<'a> {
         self.wrapped.get_driver_core_mut()
     }
 
+    fn get_mode_state(&self) -> ModeState {
+        self.wrapped.get_mode_state()
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        self.wrapped.set_mode_state(mode_state)
+    }
+
+    fn take_snapshot(&self) -> DriverSnapshot {
+        self.wrapped.take_snapshot()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        self.wrapped.restore_snapshot(snapshot)
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        self.wrapped.get_mode_status()
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        self.wrapped.get_target_points()
+    }
+
     fn next_frame(&mut self) -> BoardTransition {
         self.current_frame += 1;
 
@@ -92,6 +143,24 @@ impl<'a> Driver for RecordingDriver<'a> {
         self.wrapped.hold()
     }
 
+    fn activate_zone(&mut self) {
+        self.push_action(Action::ActivateZone);
+
+        self.wrapped.activate_zone()
+    }
+
+    fn start_fastfalling(&mut self) {
+        self.push_action(Action::StartFastfalling);
+
+        self.wrapped.start_fastfalling()
+    }
+
+    fn stop_fastfalling(&mut self) {
+        self.push_action(Action::StopFastfalling);
+
+        self.wrapped.stop_fastfalling()
+    }
+
     fn fall(&mut self) -> BoardTransition {
         self.push_action(Action::Fall);
         
@@ -105,6 +174,10 @@ impl<'a> Driver for RecordingDriver<'a> {
     }
 
     fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
-        self.wrapped.finish_transition(transition)
+        let description = format!("{:?}", transition);
+        let new_transition = self.wrapped.finish_transition(transition);
+        report_invariant_violations(self.wrapped.as_mut(), &description, &self.destination_file);
+
+        new_transition
     }
 }
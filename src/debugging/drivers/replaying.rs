@@ -2,6 +2,8 @@ use std::fs::File;
 use std::io::prelude::*;
 
 use crate::drivers::*;
+use crate::game_core::utils::point::Point;
+use crate::debugging::invariants::report_invariant_violations;
 use super::recording::*;
 
 
@@ -9,6 +11,7 @@ pub struct ReplayingDriver<'a> {
     wrapped: Box<dyn Driver + 'a>,
     current_frame: usize,
     actions: Vec<(usize, Action)>,
+    source_file: String,
 }
 
 impl<'a> ReplayingDriver<'a> {
@@ -28,6 +31,7 @@ impl<'a> ReplayingDriver<'a> {
             wrapped,
             current_frame: 0,
             actions,
+            source_file: source_file.to_string(),
         }
     }
 }
@@ -41,6 +45,30 @@ impl<'a> Driver for ReplayingDriver<'a> {
         self.wrapped.get_driver_core_mut()
     }
 
+    fn get_mode_state(&self) -> ModeState {
+        self.wrapped.get_mode_state()
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        self.wrapped.set_mode_state(mode_state)
+    }
+
+    fn take_snapshot(&self) -> DriverSnapshot {
+        self.wrapped.take_snapshot()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        self.wrapped.restore_snapshot(snapshot)
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        self.wrapped.get_mode_status()
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        self.wrapped.get_target_points()
+    }
+
     fn next_frame(&mut self) -> BoardTransition {
         let mut transitions = self.wrapped.next_frame();
 
@@ -51,19 +79,7 @@ impl<'a> Driver for ReplayingDriver<'a> {
             }
 
             let action = self.actions.pop().unwrap().1;
-            match action {
-                Action::TranslateLeft => { self.wrapped.translate_left(); }
-                Action::TranslateRight => { self.wrapped.translate_right(); }
-                Action::RotateClockwise => { self.wrapped.rotate_clockwise(); }
-                Action::RotateCounterClockwise => { self.wrapped.rotate_counterclockwise(); }
-                Action::Hold => self.wrapped.hold(),
-                Action::Fastfall => {
-                    transitions.add_from_transition(self.wrapped.fastfall());
-                },
-                Action::Fall => {
-                    transitions.add_from_transition(self.wrapped.fall());
-                },
-            }
+            transitions.add_from_transition(action.apply(self.wrapped.as_mut()));
         }
 
         transitions
@@ -95,6 +111,24 @@ impl<'a> Driver for ReplayingDriver<'a> {
         }
     }
 
+    fn activate_zone(&mut self) {
+        if self.actions.is_empty() {
+            self.wrapped.activate_zone()
+        }
+    }
+
+    fn start_fastfalling(&mut self) {
+        if self.actions.is_empty() {
+            self.wrapped.start_fastfalling()
+        }
+    }
+
+    fn stop_fastfalling(&mut self) {
+        if self.actions.is_empty() {
+            self.wrapped.stop_fastfalling()
+        }
+    }
+
     fn fall(&mut self) -> BoardTransition {
         if self.actions.is_empty() {
             self.wrapped.fall()
@@ -112,6 +146,10 @@ impl<'a> Driver for ReplayingDriver<'a> {
     }
 
     fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
-        self.wrapped.finish_transition(transition)
+        let description = format!("{:?}", transition);
+        let new_transition = self.wrapped.finish_transition(transition);
+        report_invariant_violations(self.wrapped.as_mut(), &description, &self.source_file);
+
+        new_transition
     }
 }
@@ -0,0 +1,43 @@
+use crate::drivers::*;
+use crate::game_core::board::{Board, GARBAGE_VALUE};
+use crate::game_core::cell_value::get_color;
+use crate::game_core::utils::point::Point;
+
+
+/**
+ * Draws the board from the top down, with '.' for empty cells, '#' for garbage and the
+ * color of any other cell as a hex digit.
+ */
+pub fn dump_board(board: &Board) -> String {
+    let mut dump = String::new();
+    for y in (0..board.num_active_rows() as i32).rev() {
+        dump.push_str(&format!("{:>3} ", y));
+        for x in 0..board.get_width() as i32 {
+            dump.push(match board.get_cell(Point(x, y)) {
+                None => '.',
+                Some(GARBAGE_VALUE) => '#',
+                Some(value) => std::char::from_digit(get_color(value), 16).unwrap_or('?'),
+            });
+        }
+        dump.push('\n');
+    }
+
+    dump
+}
+
+/**
+ * Reports any invariants the driver found broken during its last transition.
+ */
+pub fn report_invariant_violations(driver: &mut dyn Driver, transition: &str, replay_filename: &str) {
+    let violations = driver.get_driver_core_mut().take_invariant_violations();
+    if violations.is_empty() {
+        return
+    }
+
+    eprintln!("board invariants violated (replay {}):", replay_filename);
+    for violation in violations {
+        eprintln!("  {}", violation);
+    }
+    eprintln!("transition: {}", transition);
+    eprint!("{}", dump_board(driver.get_game_core().get_board()));
+}
@@ -1,3 +1,4 @@
 pub mod drivers;
+pub mod invariants;
 pub mod replay_menu;
 pub mod recording_manager;
\ No newline at end of file
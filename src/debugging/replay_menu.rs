@@ -9,8 +9,9 @@ use super::*;
 
 
 async fn construct_gamestate_replay<'a>(gamemode: &GameMode, factory: &mut GameStateManager<'a>, replay: String) -> Box<dyn GameState<'a> + 'a> {
+    // recordings are always made with the empty seed
     TetrisState::new(
-        Box::new(ReplayingDriver::new((gamemode.get_driver)(), &replay)),
+        Box::new(ReplayingDriver::new((gamemode.get_driver)(Vec::new()), &replay)),
         (gamemode.get_renderer)(factory.get_render_manager_factory()).build().await
     ).boxed()
 }
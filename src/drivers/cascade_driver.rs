@@ -1,12 +1,17 @@
+use serde::Deserialize;
+
 use crate::drivers::*;
 use crate::game_core::defaults;
-use super::utils::recursive_physics::calculate_sticky_falls_from_rows;
 use super::utils::tetrimino_chooser::TetriminoChooser;
 
 
+/**
+ * Gives every tetrimino its own identity, so that separate pieces never stick together.
+ */
+#[derive(Clone)]
 pub struct CascadeGenerator {
     tetrimino_chooser: TetriminoChooser,
-    current_index: usize,
+    current_index: u32,
 }
 
 impl CascadeGenerator {
@@ -19,10 +24,13 @@ impl CascadeGenerator {
 }
 
 impl TetriminoGenerator for CascadeGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
     fn next(&mut self) -> Tetrimino {
-        let num_tetriminos = self.get_tetrimino_types().len();
         let (index, tetrimino_type) = self.tetrimino_chooser.choose_tetrimino_type();
-        let values = vec![(index + (self.current_index * num_tetriminos)) as u32; 4];
+        let values = vec![with_identity(index as u32, self.current_index); 4];
         self.current_index += 1;
         tetrimino_type.instance(values)
     }
@@ -34,6 +42,21 @@ impl TetriminoGenerator for CascadeGenerator {
     fn set_seed(&mut self, seed: Vec<u8>) {
         self.tetrimino_chooser.set_seed(seed);
     }
+
+    fn set_next_identity(&mut self, next_identity: u32) {
+        self.current_index = next_identity;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value((self.tetrimino_chooser.save_state(), self.current_index)).unwrap_or_default()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        if let Ok((chooser_state, current_index)) = <(serde_json::Value, u32)>::deserialize(state) {
+            self.tetrimino_chooser.load_state(&chooser_state);
+            self.current_index = current_index;
+        }
+    }
 }
 
 pub struct CascadeDriver {
@@ -48,6 +71,7 @@ impl BuildableDriver for CascadeDriver {
             .with_tetrimino_generator(
                 CascadeGenerator::new(
                     defaults::tetriminos::TETRIMINOS))
+            .with_clear_physics(CascadePhysics::new())
     }
 
     fn build(mut builder: DriverBuilder<Self>) -> Self {
@@ -67,15 +91,36 @@ impl Driver for CascadeDriver {
     }
 
     fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
-        let (rows_deleted, _, mut new_transitions) = self.driver_core.finish_transition(transition);
-
-        if let Some(rows) = rows_deleted {
-            let falls = calculate_sticky_falls_from_rows(self.driver_core.core.get_board(), rows);
-            if !falls.is_empty() {
-                new_transitions.add_points_falling(falls);
-            }
-        }
+        let (_, _, new_transitions) = self.driver_core.finish_transition(transition);
 
         return new_transitions
     }
-}
\ No newline at end of file
+
+    // everything this mode keeps track of is in its core
+    fn get_mode_state(&self) -> ModeState {
+        ModeState::Null
+    }
+
+    fn set_mode_state(&mut self, _: &ModeState) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::classic_driver::ClassicDriver;
+
+    #[test]
+    fn configured_cascade_compacts_identities() {
+        let mut driver = DriverBuilder::<ClassicDriver>::new()
+            .with_tetrimino_generator(CascadeGenerator::new(defaults::tetriminos::TETRIMINOS))
+            .with_clear_physics(CascadePhysics::new())
+            .build();
+        driver.get_game_core_mut().map_cell_values(|value| with_identity(value, MAX_IDENTITY - 1));
+
+        driver.finish_transition(BoardTransition::new());
+
+        let core = driver.get_game_core();
+        let newest = core.get_next_tetrimino(core.get_tetrimino_queue_length() - 1);
+        assert!(get_identity(newest.values[0]) < MAX_IDENTITY / 2);
+    }
+}
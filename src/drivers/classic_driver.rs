@@ -1,27 +1,18 @@
 use crate::drivers::*;
+use super::utils::scoring::ClassicScoring;
 
 
 pub struct ClassicDriver {
-    last_clear_was_tetris: bool,
+    scoring: ClassicScoring,
     driver_core: DriverCore,
 }
 
-impl ClassicDriver {
-    fn update_score(&mut self, increment: usize) {
-        self.driver_core.score += increment;
-        let level = self.driver_core.score / 5;
-        if self.driver_core.level < level && level < 15 {
-            self.driver_core.level = level;
-        }
-    }
-}
-
 impl BuildableDriver for ClassicDriver {
     type Data = ();
 
     fn build(mut builder: DriverBuilder<Self>) -> Self where Self: Sized {
         ClassicDriver {
-            last_clear_was_tetris: false,
+            scoring: ClassicScoring::new(),
             driver_core: builder.build_core()
         }
     }
@@ -40,23 +31,19 @@ impl Driver for ClassicDriver {
         let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition); 
 
         if let Some(rows) = cleared_rows {
-            let score_update = match rows.len() {
-                1 => 1,
-                2 => 3,
-                3 => 5,
-                4 => if self.last_clear_was_tetris {
-                        12
-                    } else {
-                        self.last_clear_was_tetris = true;
-                        8
-                    }
-                _ => 0
-            };
-    
-            self.last_clear_was_tetris = false;
-            self.update_score(score_update);
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
         }
 
         new_transition
     }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state(&self.scoring)
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some(scoring) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+        }
+    }
 }
\ No newline at end of file
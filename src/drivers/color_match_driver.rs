@@ -0,0 +1,225 @@
+use rand::RngCore;
+
+use crate::drivers::*;
+use crate::game_core::defaults;
+use super::utils::recursive_physics::*;
+use super::utils::tetrimino_chooser::TetriminoChooser;
+
+
+const NUM_COLORS: u32 = 4;
+const MIN_GROUP_SIZE: usize = 4;
+
+/**
+ * Generates tetriminos where every block is given a random color.
+ */
+#[derive(Clone)]
+pub struct ColorMatchGenerator {
+    tetrimino_chooser: TetriminoChooser,
+}
+
+impl ColorMatchGenerator {
+    pub fn new(tetrimino_types: &'static [TetriminoType]) -> Box<Self> {
+        Box::new(Self {
+            tetrimino_chooser: TetriminoChooser::new(tetrimino_types),
+        })
+    }
+}
+
+impl TetriminoGenerator for ColorMatchGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
+    fn next(&mut self) -> Tetrimino {
+        let (_, tetrimino_type) = self.tetrimino_chooser.choose_tetrimino_type();
+        let seeded_rng = self.tetrimino_chooser.get_seeded_rng();
+        let values = (0..4)
+            .map(|_| seeded_rng.next_u32() % NUM_COLORS)
+            .collect::<Vec<_>>();
+
+        tetrimino_type.instance(values)
+    }
+
+    fn get_tetrimino_types(&self) -> &'static [TetriminoType] {
+        self.tetrimino_chooser.get_tetrimino_types()
+    }
+
+    fn set_seed(&mut self, seed: Vec<u8>) {
+        self.tetrimino_chooser.set_seed(seed);
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        self.tetrimino_chooser.save_state()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        self.tetrimino_chooser.load_state(state);
+    }
+}
+
+/**
+ * Rows are never cleared.  Instead, groups of connected cells with the same color
+ * are cleared, and everything left falls with sticky physics, possibly setting off
+ * a chain of further clears.
+ */
+pub struct ColorMatchDriver {
+    driver_core: DriverCore,
+    min_group_size: usize,
+    chain: usize,
+    longest_chain: usize,
+}
+
+pub struct ColorMatchDriverBuilderData {
+    min_group_size: usize,
+}
+
+impl Default for ColorMatchDriverBuilderData {
+    fn default() -> Self {
+        Self {
+            min_group_size: MIN_GROUP_SIZE
+        }
+    }
+}
+
+impl ColorMatchDriver {
+    /**
+     * Each step of a chain is worth more than the last.
+     */
+    fn chain_multiplier(chain: usize) -> usize {
+        if chain <= 1 {
+            1
+        } else {
+            8 << std::cmp::min(chain - 2, 6)
+        }
+    }
+
+    /**
+     * Looks for groups to clear, and if any are found, advances the chain and returns a
+     * transition deleting them.
+     */
+    fn clear_groups(&mut self) -> BoardTransition {
+        let groups = find_connected_groups(self.driver_core.core.get_board(), self.min_group_size);
+        let mut transition = BoardTransition::new();
+        if groups.is_empty() {
+            self.chain = 0;
+            return transition;
+        }
+
+        self.chain += 1;
+        self.longest_chain = std::cmp::max(self.longest_chain, self.chain);
+
+        let points = groups.into_iter().flatten().collect::<Vec<_>>();
+        self.driver_core.score += points.len() * 10 * Self::chain_multiplier(self.chain);
+        self.driver_core.level = std::cmp::min(self.driver_core.score / 1000, 14);
+
+        transition.add_points_deleted(points);
+        transition
+    }
+
+    /**
+     * Once a tetrimino is placed, its blocks fall apart with sticky physics, and then
+     * any groups are cleared.
+     */
+    fn tetrimino_placed(&mut self, points: Vec<Point>, mut transition: BoardTransition) -> BoardTransition {
+        transition.take_rows_deleted();
+
+        let falls = calculate_sticky_falls(self.driver_core.core.get_board(), points);
+        if !falls.is_empty() {
+            transition.add_points_falling(falls);
+        } else {
+            transition.add_from_transition(self.clear_groups());
+        }
+
+        transition
+    }
+}
+
+impl BuildableDriver for ColorMatchDriver {
+    type Data = ColorMatchDriverBuilderData;
+
+    fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
+        builder
+            .with_tetrimino_generator(
+                ColorMatchGenerator::new(defaults::tetriminos::TETRIMINOS))
+            .with_clear_physics(StickyPhysics::new())
+    }
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        Self {
+            driver_core: builder.build_core(),
+            min_group_size: builder.cont.min_group_size,
+            chain: 0,
+            longest_chain: 0,
+        }
+    }
+}
+
+impl DriverBuilder<ColorMatchDriver> {
+    pub fn _with_min_group_size(mut self, min_group_size: usize) -> Self {
+        self.cont.min_group_size = min_group_size;
+        self
+    }
+}
+
+impl Driver for ColorMatchDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        vec![
+            format!("chain: {}", self.chain),
+            format!("best chain: {}", self.longest_chain),
+        ]
+    }
+
+    fn fall(&mut self) -> BoardTransition {
+        let points = self.get_game_core().get_active_tetrimino().get_points();
+        let (added, transition) = self.driver_core.fall();
+        if added && !self.is_game_over() {
+            self.tetrimino_placed(points, transition)
+        } else {
+            transition
+        }
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        let points = self.get_game_core().get_active_tetrimino().get_points();
+        let (translation, transition) = self.driver_core.fastfall();
+        if transition.get_points_added().is_some() && !self.is_game_over() {
+            let points = points.into_iter().map(|p| p - Point::unit_y(translation)).collect::<Vec<_>>();
+            self.tetrimino_placed(points, transition)
+        } else {
+            transition
+        }
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        // after a group is cleared, everything above it falls with sticky physics
+        let (_, _, mut new_transition) = self.driver_core.finish_transition(transition);
+        // full rows are never cleared in this mode
+        new_transition.take_rows_deleted();
+
+        // once everything has settled, look for the next step of the chain
+        if new_transition.is_inert() {
+            new_transition = self.clear_groups();
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.chain, &self.longest_chain))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((chain, longest_chain)) = from_mode_state(mode_state) {
+            self.chain = chain;
+            self.longest_chain = longest_chain;
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,177 @@
+use crate::drivers::*;
+use crate::game_core::defaults;
+use crate::game_core::utils::point::Point;
+use super::utils::scoring::ClassicScoring;
+
+
+const NUM_PLAYERS: usize = 2;
+
+/**
+ * Two players share one wide board, each controlling their own piece with its own hold and
+ * queue.  The pieces collide with each other, and both players score the lines cleared.
+ */
+pub struct CoopDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+}
+
+impl CoopDriver {
+    /**
+     * Selects the player that the following controls apply to.
+     */
+    pub fn select_player(&mut self, player: usize) {
+        self.driver_core.select_piece(player);
+    }
+}
+
+impl BuildableDriver for CoopDriver {
+    type Data = ();
+
+    fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
+        builder
+            .with_width(defaults::dimensions::COOP_CELL_WIDTH)
+    }
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        let mut driver_core = builder.build_core();
+
+        // each player spawns in the middle of their own half of the board
+        let width = driver_core.core.get_board().get_width() as i32;
+        let half_width = width / NUM_PLAYERS as i32;
+        let spawn_x = |player: usize| half_width * player as i32 + half_width / 2 - width / 2;
+        driver_core.core.set_spawn_offset(Point(spawn_x(0), 0));
+        for player in 1..NUM_PLAYERS {
+            driver_core.add_piece(Point(spawn_x(player), 0));
+        }
+
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core,
+        }
+    }
+}
+
+impl Driver for CoopDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    /**
+     * Each piece falls on its own, but only one may be placed at a time.  The placed piece
+     * stays selected until its transition has finished.
+     */
+    fn next_frame(&mut self) -> BoardTransition {
+        if !self.driver_core.process_game_frame() {
+            return BoardTransition::new()
+        }
+
+        let selected = self.driver_core.core.get_selected_piece();
+        for player in 0..self.driver_core.core.get_num_pieces() {
+            self.driver_core.select_piece(player);
+            if self.driver_core.process_piece_frame() {
+                let transition = self.fall();
+                if !transition.is_inert() {
+                    return transition
+                }
+            }
+        }
+
+        self.driver_core.select_piece(selected);
+        BoardTransition::new()
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition);
+
+        if let Some(rows) = cleared_rows {
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state(&self.scoring)
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some(scoring) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns_wait_for_the_other_player() {
+        let mut driver = DriverBuilder::<CoopDriver>::new().build();
+        driver.select_player(0);
+        let spawn_position = driver.get_game_core().get_active_tetrimino().position;
+        let next_tetrimino = driver.get_game_core().get_next_tetrimino(0).clone();
+        driver.driver_core.core.translate(Point(0, -5));
+
+        // the second player moves onto the spot where the first player's next tetrimino spawns
+        driver.select_player(1);
+        driver.driver_core.core.set_active_tetrimino(next_tetrimino.as_active_instance(spawn_position));
+
+        driver.select_player(0);
+        driver.fastfall();
+        assert!(driver.driver_core.is_spawning());
+
+        // the waiting tetrimino is not in the way of the piece it is waiting on
+        driver.select_player(1);
+        for _ in 0..5 {
+            assert!(driver.driver_core.translate_right());
+        }
+
+        driver.next_frame();
+        driver.select_player(0);
+        assert!(!driver.driver_core.is_spawning());
+        assert!(!driver.get_game_core().is_spawn_blocked());
+        assert!(!driver.is_game_over());
+    }
+
+    #[test]
+    fn pieces_do_not_lock_on_each_other() {
+        let mut driver = DriverBuilder::<CoopDriver>::new().build();
+        driver.select_player(1);
+        let tetrimino = driver.get_game_core().get_active_tetrimino().clone();
+
+        // the second player's piece hangs in the air beneath the first player's piece
+        driver.select_player(0);
+        driver.driver_core.core.set_active_tetrimino(tetrimino.clone().translated(Point(0, 4)));
+        driver.select_player(1);
+        driver.driver_core.core.set_active_tetrimino(tetrimino.translated(Point(0, -6)));
+
+        // a hard drop onto the other piece leaves the tetrimino in play
+        driver.select_player(0);
+        let transition = driver.fastfall();
+        assert!(transition.get_points_added().is_none());
+        assert!(!driver.driver_core.is_spawning());
+
+        // nor does it lock however long it waits on the other piece
+        for _ in 0..200 {
+            assert!(!driver.driver_core.process_piece_frame());
+        }
+        assert!(driver.fall().get_points_added().is_none());
+        assert!(driver.get_game_core().get_board().num_filled_cells() == 0);
+
+        // once the other piece moves away, it falls the rest of the way
+        let position = driver.get_game_core().get_active_tetrimino().position;
+        driver.select_player(1);
+        for _ in 0..4 {
+            assert!(driver.driver_core.translate_left());
+        }
+        driver.select_player(0);
+        driver.fastfall();
+        assert!(driver.get_game_core().get_board().num_filled_cells() > 0);
+        assert!(driver.get_game_core().get_active_tetrimino().position != position);
+    }
+}
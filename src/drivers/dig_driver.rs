@@ -0,0 +1,105 @@
+use rand::Rng;
+
+use crate::drivers::*;
+use crate::game_core::board::Board;
+use crate::game_core::defaults;
+use super::utils::scoring::ClassicScoring;
+
+
+/**
+ * A race to dig through a field of messy garbage, which ends once every garbage
+ * row has been cleared.
+ */
+pub struct DigDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+}
+
+pub struct DigDriverBuilderData {
+    garbage_rows: usize,
+}
+
+impl Default for DigDriverBuilderData {
+    fn default() -> Self {
+        Self {
+            garbage_rows: defaults::settings::DIG_GARBAGE_ROWS
+        }
+    }
+}
+
+impl DigDriver {
+    /**
+     * Fills the bottom of the board with garbage rows, each with a single hole that
+     * never lines up with the hole in the row beneath it.
+     */
+    fn fill_garbage(board: &mut Board, rng: &mut impl Rng, garbage_rows: usize) {
+        let width = board.get_width() as i32;
+        let mut last_hole = None;
+        for row in 0..garbage_rows as i32 {
+            let mut hole = rng.gen_range(0..width);
+            while Some(hole) == last_hole {
+                hole = rng.gen_range(0..width);
+            }
+
+            board.fill_garbage_row(row, &[hole]);
+            last_hole = Some(hole);
+        }
+    }
+}
+
+impl BuildableDriver for DigDriver {
+    type Data = DigDriverBuilderData;
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        let mut rng = builder.get_seeded_rng();
+        let mut driver_core = builder.build_core();
+        Self::fill_garbage(driver_core.core.get_board_mut(), &mut rng, builder.cont.garbage_rows);
+        driver_core.core.active_tetrimino_updated();
+
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core
+        }
+    }
+}
+
+impl DriverBuilder<DigDriver> {
+    pub fn _with_garbage_rows(mut self, garbage_rows: usize) -> Self {
+        self.cont.garbage_rows = garbage_rows;
+        self
+    }
+}
+
+impl Driver for DigDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition); 
+
+        if let Some(rows) = cleared_rows {
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+
+            if self.driver_core.core.get_board().num_garbage_rows() == 0 {
+                self.driver_core.end_game(GameResult::Completed);
+            }
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state(&self.scoring)
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some(scoring) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+        }
+    }
+}
\ No newline at end of file
@@ -1,6 +1,13 @@
+use std::fs::File;
+use std::io::prelude::*;
+
+use serde::Deserialize;
+
+use crate::game_core::board::GARBAGE_VALUE;
+use crate::game_core::cell_value::get_color;
 use crate::game_core::utils::point::*;
 use crate::game_core::defaults::tetriminos::*;
-use super::utils::recursive_physics::calculate_sticky_falls_from_rows;
+use super::cascade_driver::CascadeGenerator;
 use super::*;
 
 
@@ -26,15 +33,74 @@ pub const TETRIMINOS: &[TetriminoType] = &[
 
 pub const FUSION_TETRIMINO_INDEX: usize = TETRIMINOS.len() - 1;
 
+/**
+ * A single fusion puzzle: the cell that fusion minos must reach, and the junk that stands in the way.
+ */
+#[derive(Clone, Default)]
+pub struct FusionLevel {
+    pub sink: Point,
+    pub junk: Vec<Point>,
+}
+
+#[derive(Deserialize)]
+struct FusionLevelInfo {
+    // each row of the level from the top down, where '#' is junk, 'S' is the sink, and anything else is empty
+    rows: Vec<String>,
+}
+
+impl FusionLevel {
+    fn from_info(info: FusionLevelInfo) -> Self {
+        let mut level = Self::default();
+        for (y, row) in info.rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let point = Point(x as i32, y as i32);
+                match c {
+                    '#' => level.junk.push(point),
+                    'S' => level.sink = point,
+                    _ => ()
+                }
+            }
+        }
+
+        level
+    }
+
+    /**
+     * Loads the list of levels from a json file, which holds an array of levels.
+     */
+    pub fn load_levels(filename: &str) -> Vec<Self> {
+        let mut file = File::open(filename).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+
+        let levels: Vec<FusionLevelInfo> = serde_json::from_str(&contents).unwrap();
+        levels.into_iter().map(Self::from_info).collect()
+    }
+}
+
+/**
+ * Lines containing fusion minos are not cleared, instead everything else in them is deleted
+ * and the fusion minos fall.  Each level is won by getting a fusion mino onto the sink.
+ */
 pub struct FusionDriver {
     driver_core: DriverCore,
-    sink: Point,
+    levels: Vec<FusionLevel>,
+    current_level: usize,
 }
 
-#[derive(Default)]
 pub struct FusionDriverBuilderData {
-    sink: Point,
-    junk: Vec<Point>
+    levels: Vec<FusionLevel>,
+}
+
+impl Default for FusionDriverBuilderData {
+    fn default() -> Self {
+        Self {
+            levels: vec![FusionLevel {
+                sink: Point(defaults::dimensions::CELL_WIDTH as i32 / 2, 0),
+                junk: Vec::new()
+            }]
+        }
+    }
 }
 
 impl BuildableDriver for FusionDriver {
@@ -42,32 +108,55 @@ impl BuildableDriver for FusionDriver {
 
     fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
         builder
-            .with_tetrimino_generator(BasicGenerator::new(TETRIMINOS))
+            .with_tetrimino_generator(CascadeGenerator::new(TETRIMINOS))
+            .with_clear_physics(CascadePhysics::new())
     }
 
     fn build(mut builder: DriverBuilder<Self>) -> Self where Self: Sized {
-        // use the junk to populate the board
-        Self {
-            sink: builder.cont.sink,
-            driver_core: builder
-                .build_core()
-        }
+        let mut driver = Self {
+            driver_core: builder.build_core(),
+            levels: std::mem::take(&mut builder.cont.levels),
+            current_level: 0,
+        };
+
+        driver.load_level(0);
+        driver
     }
 }
 
 impl DriverBuilder<FusionDriver> {
-    pub fn _with_sink(mut self, sink: Point) -> Self {
-        self.cont.sink = sink;
-        self
-    }
-
-    pub fn _with_junk(mut self, junk: Vec<Point>) -> Self {
-        self.cont.junk = junk;
+    pub fn with_levels(mut self, levels: Vec<FusionLevel>) -> Self {
+        self.cont.levels = levels;
         self
     }
 }
 
 impl FusionDriver {
+    fn get_sink(&self) -> Point {
+        self.levels[self.current_level].sink
+    }
+
+    /**
+     * Empties the board, and then populates it with the level's junk.
+     */
+    fn load_level(&mut self, index: usize) {
+        self.current_level = index;
+
+        let board = self.driver_core.core.get_board_mut();
+        board.clear();
+        for &point in self.levels[index].junk.iter() {
+            board.fill_point(point, GARBAGE_VALUE);
+        }
+
+        self.driver_core.core.active_tetrimino_updated();
+    }
+
+    fn is_sink_reached(&self) -> bool {
+        let board = self.driver_core.core.get_board();
+        let sink = self.get_sink();
+        board.is_point_filled(sink) && board.get_cell(sink).map(get_color) == Some(FUSION_TETRIMINO_INDEX as u32)
+    }
+
     /**
      * For each transition, if a row is supposed to fall that contains fusion points,
      * that transition is replaced with a combo of deleting + falling points transitions
@@ -87,7 +176,7 @@ impl FusionDriver {
                 let mut non_fusion_points = Vec::new();
                 non_fusion_points.reserve(board.get_width());
                 for point in (0..board.get_width()).map(|x| Point(x as i32, row)) {
-                    if board.get_cell(point).unwrap() != FUSION_TETRIMINO_INDEX as u32 {
+                    if get_color(board.get_cell(point).unwrap()) != FUSION_TETRIMINO_INDEX as u32 {
                         non_fusion_points.push(point);
                     }
                 }
@@ -116,6 +205,14 @@ impl Driver for FusionDriver {
         &mut self.driver_core
     }
 
+    fn get_mode_status(&self) -> Vec<String> {
+        vec![format!("level: {} / {}", self.current_level + 1, self.levels.len())]
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        vec![self.get_sink()]
+    }
+
     fn fall(&mut self) -> BoardTransition {
         let (added, transitions) = self.driver_core.fall();
         if added {
@@ -131,26 +228,29 @@ impl Driver for FusionDriver {
     }
 
     fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
-        let (cleared_rows, cleared_points, mut new_transitions) = self.driver_core.finish_transition(transition);
-
-        if let Some(rows) = cleared_rows {
-    //     // let falls = calculate_sticky_falls_from_rows(self.get_game_core().get_board(), rows);
-    //     // if !falls.is_empty() {
-    //     //     vec![BoardTransition::PointsFalling(falls)]
-    //     // } else {
-    //         BoardTransition::new()
-    //     // }
-        }
+        let (_, _, new_transitions) = self.driver_core.finish_transition(transition);
 
-        if let Some(points) = cleared_points {
-            let mut rows = points.into_iter().map(|p| p.y()).collect::<Vec<_>>();
-            rows.sort();
-            rows.dedup();
+        // once a fusion mino reaches the sink, move on to the next level
+        if self.is_sink_reached() {
+            if self.current_level + 1 < self.levels.len() {
+                self.load_level(self.current_level + 1);
+            } else {
+                self.driver_core.end_game(GameResult::Completed);
+            }
 
-            let falls = calculate_sticky_falls_from_rows(self.get_game_core().get_board(), rows);
-            new_transitions.add_points_falling(falls);
+            return BoardTransition::new();
         }
 
         return self.extract_fusion_points(new_transitions);
     }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state(self.current_level)
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some(current_level) = from_mode_state(mode_state) {
+            self.current_level = current_level;
+        }
+    }
 }
\ No newline at end of file
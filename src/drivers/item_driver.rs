@@ -0,0 +1,233 @@
+use rand::RngCore;
+
+use crate::drivers::*;
+use crate::game_core::board::Board;
+use crate::game_core::cell_value::*;
+use crate::game_core::defaults;
+use super::utils::recursive_physics::calculate_full_gravity_falls;
+use super::utils::scoring::ClassicScoring;
+use super::utils::tetrimino_chooser::TetriminoChooser;
+
+
+// on average, one in every ITEM_CHANCE tetriminos carries an item
+const ITEM_CHANCE: u32 = 6;
+
+/**
+ * Generates the regular tetriminos, occasionally tagging one of their blocks with an item.
+ */
+#[derive(Clone)]
+pub struct ItemGenerator {
+    tetrimino_chooser: TetriminoChooser,
+}
+
+impl ItemGenerator {
+    pub fn new(tetrimino_types: &'static [TetriminoType]) -> Box<Self> {
+        Box::new(Self {
+            tetrimino_chooser: TetriminoChooser::new(tetrimino_types),
+        })
+    }
+}
+
+impl TetriminoGenerator for ItemGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
+    fn next(&mut self) -> Tetrimino {
+        let (index, tetrimino_type) = self.tetrimino_chooser.choose_tetrimino_type();
+        let mut values = vec![index as u32; 4];
+
+        let seeded_rng = self.tetrimino_chooser.get_seeded_rng();
+        if seeded_rng.next_u32().is_multiple_of(ITEM_CHANCE) {
+            let block = seeded_rng.next_u32() as usize % values.len();
+            let item = Item::ALL[seeded_rng.next_u32() as usize % Item::ALL.len()];
+            values[block] = with_item(values[block], item as u32);
+        }
+
+        tetrimino_type.instance(values)
+    }
+
+    fn get_tetrimino_types(&self) -> &'static [TetriminoType] {
+        self.tetrimino_chooser.get_tetrimino_types()
+    }
+
+    fn set_seed(&mut self, seed: Vec<u8>) {
+        self.tetrimino_chooser.set_seed(seed);
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        self.tetrimino_chooser.save_state()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        self.tetrimino_chooser.load_state(state);
+    }
+}
+
+/**
+ * Classic rules, except that items trigger whenever the row they are in is cleared.
+ */
+pub struct ItemDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+    // set when a gravity item was triggered, and the stack should collapse once the current deletions finish
+    collapse_pending: bool,
+}
+
+impl ItemDriver {
+    /**
+     * Finds every item in the rows about to be cleared, and returns the points that their
+     * effects will delete, along with whether or not the stack should collapse.
+     */
+    fn trigger_items(board: &Board, rows: &[i32]) -> (Vec<Point>, bool) {
+        let mut points = Vec::new();
+        let mut collapse = false;
+
+        for &row in rows.iter() {
+            for x in 0..board.get_width() as i32 {
+                let item_point = Point(x, row);
+                let value = match board.get_cell(item_point) {
+                    Some(value) => value,
+                    None => continue
+                };
+
+                match Item::from_value(value) {
+                    Some(Item::Bomb) => {
+                        for dy in -1..=1 {
+                            for dx in -1..=1 {
+                                points.push(item_point + Point(dx, dy));
+                            }
+                        }
+                    },
+                    Some(Item::Laser) => {
+                        points.extend((0..board.num_active_rows() as i32).map(|y| Point(x, y)));
+                    },
+                    Some(Item::ColorBomb) => {
+                        for y in 0..board.num_active_rows() as i32 {
+                            for x in 0..board.get_width() as i32 {
+                                let point = Point(x, y);
+                                if board.get_cell(point).map(without_item) == Some(without_item(value)) {
+                                    points.push(point);
+                                }
+                            }
+                        }
+                    },
+                    Some(Item::Gravity) => collapse = true,
+                    None => ()
+                }
+            }
+        }
+
+        // only filled cells outside of the cleared rows need to be deleted separately
+        points.retain(|p| {
+            board.is_on_board(*p) && (p.y() as usize) < board.num_active_rows() && board.is_point_filled(*p) && !rows.contains(&p.y())
+        });
+        points.sort_by_key(|p| (p.y(), p.x()));
+        points.dedup();
+
+        (points, collapse)
+    }
+}
+
+impl BuildableDriver for ItemDriver {
+    type Data = ();
+
+    fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
+        builder
+            .with_tetrimino_generator(
+                ItemGenerator::new(defaults::tetriminos::TETRIMINOS))
+    }
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core: builder.build_core(),
+            collapse_pending: false,
+        }
+    }
+}
+
+impl Driver for ItemDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let triggered = transition.get_rows_deleted()
+            .map(|rows| Self::trigger_items(self.driver_core.core.get_board(), rows));
+        let original_rows = transition.get_rows_deleted().cloned().unwrap_or(Vec::new());
+
+        let (cleared_rows, cleared_points, mut new_transition) = self.driver_core.finish_transition(transition);
+
+        if let Some(rows) = cleared_rows {
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+        }
+
+        // once the item deletions have finished, the cells above them fall into the gaps, unless
+        // a gravity item was triggered alongside them, in which case the whole stack collapses
+        if cleared_points.is_some() && self.collapse_pending {
+            self.collapse_pending = false;
+            new_transition.take_points_falling();
+            new_transition.add_points_falling(calculate_full_gravity_falls(self.driver_core.core.get_board()));
+        }
+
+        if let Some((points, collapse)) = triggered {
+            // shift the points down to account for the rows that were just cleared beneath them
+            let points = points
+                .into_iter()
+                .map(|p| p - Point::unit_y(original_rows.iter().filter(|&&row| row < p.y()).count() as i32))
+                .collect::<Vec<_>>();
+
+            if !points.is_empty() {
+                self.collapse_pending |= collapse;
+                new_transition.add_points_deleted(points);
+            } else if collapse {
+                new_transition.add_points_falling(calculate_full_gravity_falls(self.driver_core.core.get_board()));
+            }
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.scoring, &self.collapse_pending))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((scoring, collapse_pending)) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+            self.collapse_pending = collapse_pending;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn laser_can_empty_the_board() {
+        let mut driver = DriverBuilder::<ItemDriver>::new().build();
+        let board = driver.get_game_core_mut().get_board_mut();
+        let width = board.get_width() as i32;
+        for x in 0..width {
+            let value = if x == 0 { with_item(0, Item::Laser as u32) } else { 0 };
+            board.fill_point(Point(x, 0), value);
+        }
+        board.fill_point(Point(0, 1), 0);
+
+        // the laser deletes the cell left above the cleared row, which leaves nothing on the board
+        let mut transition = driver.finish_transition(BoardTransition::new().with_rows_deleted(vec![0]));
+        while !transition.is_inert() {
+            transition = driver.finish_transition(transition);
+        }
+
+        let board = driver.get_game_core().get_board();
+        assert_eq!(board.num_active_rows(), 0);
+        assert!(board.find_inconsistencies().is_empty());
+    }
+}
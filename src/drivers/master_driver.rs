@@ -0,0 +1,320 @@
+use crate::drivers::*;
+use crate::game_core::defaults::settings::{FRAMES_PER_SECOND, FADING_STACK_FRAMES};
+
+
+const MAX_LEVEL: usize = 999;
+const SECTION_LENGTH: usize = 100;
+const CREDITS_DURATION: usize = 55 * FRAMES_PER_SECOND;
+// the grand master grade requires finishing the game within 13:30
+const GRAND_MASTER_TIME_LIMIT: usize = (13 * 60 + 30) * FRAMES_PER_SECOND;
+
+/**
+ * Gravity, in 1/256ths of a cell per frame, starting at the given level.
+ */
+const GRAVITY_CURVE: &[(usize, usize)] = &[
+    (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48), (90, 64),
+    (100, 80), (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32), (230, 64),
+    (233, 96), (236, 128), (239, 160), (243, 192), (247, 224), (251, 256), (300, 512),
+    (330, 768), (360, 1024), (400, 1280), (420, 1024), (450, 768), (500, 5120),
+];
+
+/**
+ * The (spawn delay, lock delay) in frames, starting at the given level.
+ */
+const DELAY_CURVE: &[(usize, (usize, usize))] = &[
+    (0, (25, 30)),
+    (700, (16, 30)),
+    (800, (12, 30)),
+    (900, (12, 17)),
+];
+
+/**
+ * For each internal grade, the (displayed grade, decay rate, points for 1/2/3/4 lines).
+ */
+const GRADE_TABLE: &[(&str, usize, [usize; 4])] = &[
+    ("9", 125, [10, 20, 40, 50]),
+    ("8", 80, [10, 20, 30, 40]),
+    ("7", 80, [10, 20, 30, 40]),
+    ("6", 50, [10, 15, 30, 40]),
+    ("5", 45, [10, 15, 20, 40]),
+    ("4", 45, [5, 15, 20, 30]),
+    ("4", 45, [5, 10, 20, 30]),
+    ("3", 40, [5, 10, 15, 30]),
+    ("3", 40, [5, 10, 15, 30]),
+    ("2", 40, [5, 10, 15, 30]),
+    ("2", 40, [2, 12, 13, 30]),
+    ("2", 40, [2, 12, 13, 30]),
+    ("1", 30, [2, 12, 13, 30]),
+    ("1", 30, [2, 12, 13, 30]),
+    ("1", 30, [2, 12, 13, 30]),
+    ("S1", 20, [2, 12, 13, 30]),
+    ("S2", 20, [2, 12, 13, 30]),
+    ("S3", 20, [2, 12, 13, 30]),
+    ("S4", 20, [2, 12, 13, 30]),
+    ("S4", 20, [2, 12, 13, 30]),
+    ("S4", 15, [2, 12, 13, 30]),
+    ("S5", 15, [2, 12, 13, 30]),
+    ("S5", 15, [2, 12, 13, 30]),
+    ("S6", 15, [2, 12, 13, 30]),
+    ("S6", 15, [2, 12, 13, 30]),
+    ("S7", 15, [2, 12, 13, 30]),
+    ("S7", 15, [2, 12, 13, 30]),
+    ("S8", 15, [2, 12, 13, 30]),
+    ("S8", 15, [2, 12, 13, 30]),
+    ("S9", 15, [2, 12, 13, 30]),
+    ("S9", 10, [2, 12, 13, 30]),
+    ("S9", 10, [2, 12, 13, 30]),
+];
+
+fn lookup_curve<T: Copy>(curve: &[(usize, T)], level: usize) -> T {
+    curve
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= level)
+        .map(|(_, value)| *value)
+        .unwrap_or(curve[0].1)
+}
+
+/**
+ * Converts the gravity curve into the number of frames per cell fallen.  Soft
+ * dropping is never slower than one cell per frame.
+ */
+pub fn calculate_master_gravity(level: usize, fastfalling: bool) -> f32 {
+    let gravity = 256.0 / lookup_curve(GRAVITY_CURVE, level) as f32;
+    if fastfalling {
+        gravity.min(1.0)
+    } else {
+        gravity
+    }
+}
+
+/**
+ * A mode that runs from level 0 to 999, with levels gained for every tetrimino
+ * and line, stops at the end of every section, an internal grading system and a
+ * credits roll once the final level is reached.
+ */
+pub struct MasterDriver {
+    driver_core: DriverCore,
+
+    internal_grade: usize,
+    grade_points: usize,
+    frames_since_decay: usize,
+    combo: usize,
+
+    section_start: usize,
+    section_times: Vec<usize>,
+    credits_start: Option<usize>,
+}
+
+impl MasterDriver {
+    fn get_grade(&self) -> &'static str {
+        let grade = GRADE_TABLE[self.internal_grade].0;
+        let finished_in_time = self.section_times.iter().sum::<usize>() <= GRAND_MASTER_TIME_LIMIT;
+        if grade == "S9" && self.credits_start.is_some() && finished_in_time {
+            "GM"
+        } else {
+            grade
+        }
+    }
+
+    /**
+     * Sets the level, updating the section times and the level specific delays.
+     */
+    fn set_level(&mut self, level: usize) {
+        let level = std::cmp::min(level, MAX_LEVEL);
+        let old_level = self.driver_core.level;
+        self.driver_core.level = level;
+
+        let frames_elapsed = self.driver_core.frames_elapsed;
+        if level / SECTION_LENGTH > old_level / SECTION_LENGTH || (level == MAX_LEVEL && old_level != MAX_LEVEL) {
+            self.section_times.push(frames_elapsed - self.section_start);
+            self.section_start = frames_elapsed;
+        }
+
+        if level == MAX_LEVEL && self.credits_start.is_none() {
+            self.credits_start = Some(frames_elapsed);
+
+            // the credits are played with a fading stack, or an invisible one on the way to grand master
+            self.driver_core.stack_visibility = if self.get_grade() == "GM" {
+                StackVisibility::invisible(0)
+            } else {
+                StackVisibility::fading(FADING_STACK_FRAMES, 0)
+            };
+        }
+
+        let (spawn_delay, lock_delay) = lookup_curve(DELAY_CURVE, level);
+        self.driver_core.spawn_delay = spawn_delay;
+        self.driver_core.lock_delay = lock_delay;
+    }
+
+    /**
+     * Placing a tetrimino advances the level, unless the level is at the end of a section
+     */
+    fn piece_placed(&mut self) {
+        let level = self.driver_core.level;
+        if level % SECTION_LENGTH != SECTION_LENGTH - 1 && level != MAX_LEVEL - 1 && self.credits_start.is_none() {
+            self.set_level(level + 1);
+        }
+    }
+
+    fn lines_cleared(&mut self, num_lines: usize) {
+        if num_lines == 0 {
+            self.combo = 0;
+            return
+        }
+
+        self.combo += 1;
+        if self.credits_start.is_none() {
+            self.set_level(self.driver_core.level + num_lines);
+        }
+
+        // award grade points, scaled by the combo and the level
+        let base_points = GRADE_TABLE[self.internal_grade].2[std::cmp::min(num_lines, 4) - 1];
+        let combo_multiplier = if num_lines > 1 { 10 + std::cmp::min(self.combo - 1, 10) } else { 10 };
+        let level_multiplier = 1 + self.driver_core.level / 250;
+        self.grade_points += (base_points * combo_multiplier).div_ceil(10) * level_multiplier;
+        self.frames_since_decay = 0;
+
+        if self.grade_points >= 100 {
+            self.grade_points = 0;
+            self.internal_grade = std::cmp::min(self.internal_grade + 1, GRADE_TABLE.len() - 1);
+        }
+
+        self.driver_core.score += num_lines * num_lines * (self.driver_core.level + 1);
+    }
+
+    /**
+     * Grade points slowly decay while the player is not clearing lines.
+     */
+    fn decay_grade_points(&mut self) {
+        if self.combo > 0 || self.grade_points == 0 {
+            return
+        }
+
+        self.frames_since_decay += 1;
+        if self.frames_since_decay >= GRADE_TABLE[self.internal_grade].1 {
+            self.frames_since_decay = 0;
+            self.grade_points -= 1;
+        }
+    }
+}
+
+impl BuildableDriver for MasterDriver {
+    type Data = ();
+
+    fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
+        builder
+            .with_get_gravity(calculate_master_gravity)
+    }
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        let mut driver = Self {
+            driver_core: builder.build_core(),
+
+            internal_grade: 0,
+            grade_points: 0,
+            frames_since_decay: 0,
+            combo: 0,
+
+            section_start: 0,
+            section_times: Vec::new(),
+            credits_start: None,
+        };
+
+        driver.set_level(0);
+        driver
+    }
+}
+
+impl Driver for MasterDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        let mut status = vec![format!("grade: {}", self.get_grade())];
+        for (i, frames) in self.section_times.iter().enumerate() {
+            let seconds = frames / FRAMES_PER_SECOND;
+            status.push(format!("{:03}: {}:{:02}", i * SECTION_LENGTH, seconds / 60, seconds % 60));
+        }
+
+        if let Some(credits_start) = self.credits_start {
+            let remaining = CREDITS_DURATION.saturating_sub(self.driver_core.frames_elapsed - credits_start);
+            status.push(format!("credits: {}", remaining / FRAMES_PER_SECOND));
+        }
+
+        status
+    }
+
+    fn next_frame(&mut self) -> BoardTransition {
+        let transition = if self.driver_core.process_frame() {
+            self.fall()
+        } else {
+            BoardTransition::new()
+        };
+
+        self.decay_grade_points();
+        if let Some(credits_start) = self.credits_start {
+            if self.driver_core.frames_elapsed - credits_start >= CREDITS_DURATION {
+                self.driver_core.end_game(GameResult::Completed);
+            }
+        }
+
+        transition
+    }
+
+    fn fall(&mut self) -> BoardTransition {
+        let (added, transition) = self.driver_core.fall();
+        if added {
+            self.piece_placed();
+            if transition.get_rows_deleted().is_none() {
+                self.lines_cleared(0);
+            }
+        }
+
+        transition
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        let (_, transition) = self.driver_core.fastfall();
+        if transition.get_points_added().is_some() {
+            self.piece_placed();
+            if transition.get_rows_deleted().is_none() {
+                self.lines_cleared(0);
+            }
+        }
+
+        transition
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition); 
+
+        if let Some(rows) = cleared_rows {
+            let num_lines = self.driver_core.count_lines(rows.len());
+            self.lines_cleared(num_lines);
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.internal_grade, &self.grade_points, &self.frames_since_decay, &self.combo, &self.section_start, &self.section_times, &self.credits_start))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((internal_grade, grade_points, frames_since_decay, combo, section_start, section_times, credits_start)) = from_mode_state(mode_state) {
+            self.internal_grade = internal_grade;
+            self.grade_points = grade_points;
+            self.frames_since_decay = frames_since_decay;
+            self.combo = combo;
+            self.section_start = section_start;
+            self.section_times = section_times;
+            self.credits_start = credits_start;
+        }
+    }
+}
\ No newline at end of file
@@ -1,20 +1,41 @@
-use rand::{thread_rng, Rng};
+use std::collections::HashMap;
 
-use crate::game_core::GameCore;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+
+use crate::game_core::{GameCore, GameCoreState};
 use crate::game_core::utils::point::Point;
 use crate::game_core::utils::orientations::Direction;
 use crate::game_core::tetriminos::*;
-use crate::game_core::board::Board;
+use crate::game_core::board::{Board, GARBAGE_VALUE};
+use crate::game_core::cell_value::{get_identity, with_identity, MAX_IDENTITY};
 use crate::game_core::defaults;
+use utils::tetrimino_chooser::SeededRng;
 
 pub mod utils;
 pub mod classic_driver;
 pub mod sticky_driver;
 pub mod cascade_driver;
 pub mod fusion_driver;
+pub mod ultra_driver;
+pub mod dig_driver;
+pub mod survival_driver;
+pub mod master_driver;
+pub mod color_match_driver;
+pub mod item_driver;
+pub mod perfect_clear_driver;
+pub mod puzzle_driver;
+pub mod zone_driver;
+pub mod coop_driver;
 
 pub use utils::board_transition::*;
+pub use utils::clear_physics::*;
+
 
+// once piece identities get this close to overflowing, they are renumbered from zero
+const IDENTITY_COMPACTION_MARGIN: u32 = 1024;
+// at most this many invariant violations are kept until they are taken, the first ones being the most telling
+const MAX_INVARIANT_VIOLATIONS: usize = 100;
 
 pub trait Driver {
     fn get_driver_core(&self) -> &DriverCore;
@@ -35,6 +56,44 @@ pub trait Driver {
     fn get_level(&self) -> usize {
         self.get_driver_core().level
     }
+    fn get_lines_cleared(&self) -> usize {
+        self.get_driver_core().lines_cleared
+    }
+    fn get_frames_elapsed(&self) -> usize {
+        self.get_driver_core().frames_elapsed
+    }
+    fn get_garbage_lines_cleared(&self) -> usize {
+        self.get_driver_core().garbage_lines_cleared
+    }
+    fn get_time_remaining(&self) -> Option<usize> {
+        self.get_driver_core().time_remaining()
+    }
+    fn get_stack_visibility(&self) -> StackVisibility {
+        self.get_driver_core().stack_visibility
+    }
+    fn is_stack_revealed(&self) -> bool {
+        self.get_driver_core().is_stack_revealed()
+    }
+    fn get_game_result(&self) -> Option<GameResult> {
+        self.get_driver_core().game_result
+    }
+    fn is_game_over(&self) -> bool {
+        self.get_game_result().is_some()
+    }
+
+    /*
+     * Any extra, mode specific information to display alongside the game.
+     */
+    fn get_mode_status(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /*
+     * Cells that the player is trying to reach, which are highlighted on the board.
+     */
+    fn get_target_points(&self) -> Vec<Point> {
+        Vec::new()
+    }
 
     /*
      * Engine for getting the next frame.
@@ -69,6 +128,9 @@ pub trait Driver {
         self.get_driver_core_mut().hold()
     }
 
+    // modes with a zone meter use this to enter the zone
+    fn activate_zone(&mut self) {}
+
     fn start_fastfalling(&mut self) {
         self.get_driver_core_mut().start_fastfalling();
     }
@@ -87,11 +149,174 @@ pub trait Driver {
 
 
     fn finish_transition(&mut self, _: BoardTransition) -> BoardTransition;
+
+    /*
+     * Everything a mode keeps track of outside of its core, such as its scoring and progress.
+     * Settings that the mode was built with are left out.
+     */
+    fn get_mode_state(&self) -> ModeState;
+    fn set_mode_state(&mut self, mode_state: &ModeState);
+
+    /*
+     * Snapshots, for rolling a game back to an earlier frame.
+     */
+    fn take_snapshot(&self) -> DriverSnapshot {
+        DriverSnapshot {
+            driver_core: self.get_driver_core().clone(),
+            mode_state: self.get_mode_state(),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        *self.get_driver_core_mut() = snapshot.driver_core.clone();
+        self.set_mode_state(&snapshot.mode_state);
+    }
+
+    /*
+     * The whole state of the game, which can be sent elsewhere and loaded into a driver built
+     * for the same mode with the same seed.
+     */
+    fn save_state(&self) -> DriverState {
+        DriverState {
+            driver_core: self.get_driver_core().save_state(),
+            mode_state: self.get_mode_state(),
+        }
+    }
+
+    fn load_state(&mut self, state: &DriverState) {
+        self.get_driver_core_mut().load_state(&state.driver_core);
+        self.set_mode_state(&state.mode_state);
+    }
+}
+
+/**
+ * A mode's own state, serialized so that every mode can be snapshotted the same way.
+ */
+pub type ModeState = serde_json::Value;
+
+pub fn to_mode_state(state: impl Serialize) -> ModeState {
+    serde_json::to_value(state).unwrap_or_default()
+}
+
+/**
+ * Reads back a mode state, which is None if it was taken from a different mode.
+ */
+pub fn from_mode_state<T: DeserializeOwned>(mode_state: &ModeState) -> Option<T> {
+    T::deserialize(mode_state).ok()
+}
+
+/**
+ * A copy of a driver's state at some frame.
+ */
+#[derive(Clone)]
+pub struct DriverSnapshot {
+    driver_core: DriverCore,
+    mode_state: ModeState,
+}
+
+/**
+ * A driver's state without any of the settings it was built with, which unlike a snapshot can
+ * be serialized.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DriverState {
+    driver_core: DriverCoreState,
+    mode_state: ModeState,
+}
+
+/**
+ * The different ways that a game can come to an end.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GameResult {
+    // the stack overflowed the top of the board
+    ToppedOut,
+    // the time limit of the mode ran out
+    TimeUp,
+    // the objective of the mode was achieved
+    Completed,
+    // the objective of the mode can no longer be achieved
+    Failed,
+}
+
+/**
+ * The outcome of placing a single tetrimino, used to decide how much garbage it sends.
+ */
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LockResult {
+    pub lines_cleared: usize,
+    // the tetrimino was a T spun into place
+    pub t_spin: bool,
+    pub perfect_clear: bool,
+}
+
+/**
+ * Describes how long placed cells remain visible for.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct StackVisibility {
+    // the number of frames a placed cell takes to fade out, or None if it never does
+    pub fade_frames: Option<usize>,
+    // the number of frames the whole stack is revealed for after clearing a line
+    pub flash_frames: usize,
+}
+
+impl StackVisibility {
+    pub fn visible() -> Self {
+        Self {
+            fade_frames: None,
+            flash_frames: 0
+        }
+    }
+
+    pub fn fading(fade_frames: usize, flash_frames: usize) -> Self {
+        Self {
+            fade_frames: Some(fade_frames),
+            flash_frames
+        }
+    }
+
+    pub fn invisible(flash_frames: usize) -> Self {
+        Self::fading(0, flash_frames)
+    }
+}
+
+/**
+ * The timers of a piece that is not selected, in modes with several active pieces.
+ */
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+struct PieceTimers {
+    frames_since_drop: f32,
+    frames_since_lock_delay: usize,
+    lock_delayed: bool,
+    fastfalling: bool,
+    frames_until_spawn: usize,
+    can_hold: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct DriverCoreState {
+    core: GameCoreState,
+    frames_since_drop: f32,
+    level: usize,
+    score: usize,
+    lines_cleared: usize,
+    garbage_lines_cleared: usize,
+    frames_elapsed: usize,
+    game_result: Option<GameResult>,
+    last_clear_frame: Option<usize>,
+    frames_since_lock_delay: usize,
+    lock_delayed: bool,
+    fastfalling: bool,
+    frames_until_spawn: usize,
+    parked_timers: Vec<Option<PieceTimers>>,
+    can_hold: bool,
 }
 
 /**
  * Contains basic functionality that all drivers will share.
  */
+#[derive(Clone)]
 pub struct DriverCore {
     core: GameCore,
 
@@ -100,12 +325,41 @@ pub struct DriverCore {
 
     level: usize,
     score: usize,
+    lines_cleared: usize,
+    garbage_lines_cleared: usize,
+
+    // the number of frames that have been processed, and the optional number of frames the game may last
+    frames_elapsed: usize,
+    time_limit: Option<usize>,
+    game_result: Option<GameResult>,
+
+    stack_visibility: StackVisibility,
+    last_clear_frame: Option<usize>,
 
     lock_delay: usize,
     frames_since_lock_delay: usize,
     lock_delayed: bool,
     fastfalling: bool,
 
+    // the number of frames to wait after a tetrimino is placed before the next one becomes active
+    spawn_delay: usize,
+    frames_until_spawn: usize,
+
+    // decides how the board falls after a clear
+    clear_physics: Box<dyn ClearPhysics>,
+
+    // problems found with the board after a transition, only collected in debug builds
+    invariant_violations: Vec<String>,
+
+    // every placement since they were last taken, only collected once something asks for them
+    lock_results: Option<Vec<LockResult>>,
+
+    // the timers of every piece, with an empty slot for the selected piece
+    parked_timers: Vec<Option<PieceTimers>>,
+
+    // the seed the driver was built with, so that the same game can be built elsewhere
+    rng_seed: Vec<u8>,
+
     can_hold: bool
 }
 
@@ -114,6 +368,45 @@ impl DriverCore {
      * Processes another frame, and returns a boolean indicating if a piece should fall 
      */
     pub fn process_frame(&mut self) -> bool {
+        self.process_game_frame() && self.process_piece_frame()
+    }
+
+    /**
+     * Advances the game clock by a frame, returning false if the game is over.
+     */
+    pub fn process_game_frame(&mut self) -> bool {
+        if self.game_result.is_some() {
+            return false
+        }
+
+        self.frames_elapsed += 1;
+        self.core.get_board_mut().set_current_frame(self.frames_elapsed);
+        if self.time_remaining() == Some(0) {
+            self.end_game(GameResult::TimeUp);
+            return false
+        }
+
+        true
+    }
+
+    /**
+     * Applies a frame of gravity to the selected piece, returning true if it should fall.
+     */
+    pub fn process_piece_frame(&mut self) -> bool {
+        if self.frames_until_spawn > 0 {
+            // the last frame of the spawn delay lasts until no other piece covers the spawn point
+            let blocked = self.core.is_spawn_blocked();
+            if !blocked && self.core.is_waiting_to_spawn() && !self.core.does_active_tetrimino_fit() {
+                // the piece it waited on was placed over the spawn point
+                self.end_game(GameResult::ToppedOut);
+            }
+            self.core.set_waiting_to_spawn(blocked);
+            if !blocked || self.frames_until_spawn > 1 {
+                self.frames_until_spawn -= 1;
+            }
+            return false
+        }
+
         if self.lock_delayed {
             self.frames_since_lock_delay += 1;
             if self.frames_since_lock_delay > self.lock_delay {
@@ -122,10 +415,17 @@ impl DriverCore {
             }            
         } else {
             self.frames_since_drop += 1.0;
-            let gravity = (self.get_gravity)(self.level, self.fastfalling);
+            // tetriminos that fall in half steps fall twice as often, to keep up with their size
+            let steps_per_block = self.core.get_piece_scale() / self.core.get_movement_step();
+            let gravity = (self.get_gravity)(self.level, self.fastfalling) / steps_per_block as f32;
             while self.frames_since_drop > gravity {
                 self.frames_since_drop -= gravity;
                 if !self.core.try_fall() {
+                    if !self.core.is_resting_on_board() {
+                        // another piece holds this one up, so it keeps waiting to fall
+                        self.frames_since_drop = 0.0;
+                        break;
+                    }
                     self.lock_delayed = true;
                     self.frames_since_lock_delay = 0;
                     self.frames_since_drop = 0.0;
@@ -137,20 +437,57 @@ impl DriverCore {
         false
     }
 
+    pub fn time_remaining(&self) -> Option<usize> {
+        self.time_limit.map(|limit| limit.saturating_sub(self.frames_elapsed))
+    }
+
+    /**
+     * Converts a number of cleared rows into a number of lines.  When tetriminos are scaled up,
+     * each line of blocks is several rows of cells tall.
+     */
+    pub fn count_lines(&self, num_rows: usize) -> usize {
+        let scale = self.core.get_piece_scale() as usize;
+        num_rows.div_ceil(scale)
+    }
+
+    /**
+     * The whole stack is shown once the game is over, and briefly after clearing lines.
+     */
+    pub fn is_stack_revealed(&self) -> bool {
+        self.game_result.is_some() || self.last_clear_frame
+            .is_some_and(|frame| self.frames_elapsed < frame + self.stack_visibility.flash_frames)
+    }
+
+    /**
+     * Ends the game, unless it has already ended for another reason.
+     */
+    pub fn end_game(&mut self, result: GameResult) {
+        if self.game_result.is_none() {
+            self.game_result = Some(result);
+        }
+    }
+
+    /**
+     * While the next tetrimino is waiting to spawn, it cannot be controlled.
+     */
+    pub fn is_spawning(&self) -> bool {
+        self.frames_until_spawn > 0
+    }
+
     fn translate_left(&mut self) -> bool {
-        self.core.translate(Point(-1, 0))
+        !self.is_spawning() && self.core.translate(Point(-self.core.get_movement_step(), 0))
     }
 
     fn translate_right(&mut self) -> bool {
-        self.core.translate(Point(1, 0))
+        !self.is_spawning() && self.core.translate(Point(self.core.get_movement_step(), 0))
     }
 
     fn rotate_clockwise(&mut self) -> bool {
-        self.core.rotate(Direction::Clockwise)
+        !self.is_spawning() && self.core.rotate(Direction::Clockwise)
     }
 
     fn rotate_counterclockwise(&mut self) -> bool {
-        self.core.rotate(Direction::CounterClockwise)
+        !self.is_spawning() && self.core.rotate(Direction::CounterClockwise)
     }
 
     fn start_fastfalling(&mut self) {
@@ -163,10 +500,23 @@ impl DriverCore {
     }
 
     fn hold(&mut self) {
-        if self.can_hold {
+        if self.can_hold && !self.is_spawning() {
             self.core.hold();
             self.can_hold = false;
             self.lock_delayed = false;
+            self.start_spawn_delay(0);
+        }
+    }
+
+    /**
+     * Waits before the next tetrimino can be controlled.  A tetrimino that spawns into another
+     * player's piece keeps waiting until that piece moves away.
+     */
+    fn start_spawn_delay(&mut self, spawn_delay: usize) {
+        self.frames_until_spawn = spawn_delay;
+        if self.core.is_spawn_blocked() {
+            self.core.set_waiting_to_spawn(true);
+            self.frames_until_spawn = self.frames_until_spawn.max(1);
         }
     }
 
@@ -174,12 +524,17 @@ impl DriverCore {
         self.lock_delayed = false;
         let tetrimino_points = self.core.get_active_tetrimino().get_points();
         let (added, rows_deleted) = self.core.fall();
+        // a placed tetrimino that does not fit on the board ends the game
+        if added && rows_deleted.is_none() {
+            self.end_game(GameResult::ToppedOut);
+        }
 
         let mut transition = BoardTransition::new()
             .with_rows_deleted(rows_deleted.unwrap_or(Vec::new()));
 
         if added {
             self.can_hold = true;
+            self.start_spawn_delay(self.spawn_delay);
             transition.add_points_added(tetrimino_points);
         }
 
@@ -187,11 +542,24 @@ impl DriverCore {
     }
 
     fn fastfall(&mut self) -> (i32, BoardTransition) {
+        if self.is_spawning() {
+            return (0, BoardTransition::new())
+        }
+
         self.lock_delayed = false;
-        self.can_hold = true;
  
         let tetrimino_points = self.core.get_active_tetrimino().get_points();
-        let (translation, rows_deleted) = self.core.fastfall();
+        let (translation, added, rows_deleted) = self.core.fastfall();
+        if !added {
+            self.frames_since_drop = 0.0;
+            return (translation, BoardTransition::new())
+        }
+
+        self.can_hold = true;
+        self.start_spawn_delay(self.spawn_delay);
+        if rows_deleted.is_none() {
+            self.end_game(GameResult::ToppedOut);
+        }
 
         let transition = BoardTransition::new()
             .with_points_added(tetrimino_points)
@@ -202,11 +570,16 @@ impl DriverCore {
 
     fn finish_transition(&mut self, mut transition: BoardTransition) -> (Option<Vec<i32>>, Option<Vec<Point>>, BoardTransition) {
         let mut chain_transition = BoardTransition::new();
+        let filled_cells = self.core.get_board().num_filled_cells();
+        let placed = transition.get_points_added().is_some();
 
         let mut deleted_rows = None;
         if let Some(mut rows) = transition.get_rows_deleted().cloned() {
             let board = self.core.get_board_mut();
+            self.garbage_lines_cleared += rows.iter().filter(|&&row| board.is_garbage_row(row)).count();
             board.clear_rows(rows.clone());
+            self.lines_cleared += self.count_lines(rows.len());
+            self.last_clear_frame = Some(self.frames_elapsed);
             rows.sort();
             for i in 0..rows.len() {
                 rows[i] -= i as i32;
@@ -244,10 +617,191 @@ impl DriverCore {
             chain_transition.add_rows_deleted(full_rows);
         }
 
+        if deleted_rows.is_some() || deleted_points.is_some() {
+            let falls = self.clear_physics.calculate_falls(
+                self.core.get_board(),
+                deleted_rows.as_deref().unwrap_or(&[]),
+                deleted_points.as_deref().unwrap_or(&[]));
+            if !falls.is_empty() {
+                chain_transition.add_points_falling(falls);
+            }
+        }
+
+        if placed && self.lock_results.is_some() {
+            let lines_cleared = deleted_rows.as_ref().map_or(0, |rows| self.count_lines(rows.len()));
+            let lock_result = LockResult {
+                lines_cleared,
+                t_spin: self.core.get_last_placement().t_spin,
+                perfect_clear: lines_cleared > 0 && self.core.get_board().num_active_rows() == 0,
+            };
+            if let Some(lock_results) = self.lock_results.as_mut() {
+                lock_results.push(lock_result);
+            }
+        }
+
+        self.compact_identities_if_needed();
+
+        if cfg!(feature = "debug") {
+            self.check_invariants(filled_cells, &deleted_rows, &deleted_points, &chain_transition);
+        }
+
         (deleted_rows, deleted_points, chain_transition)
     }
+
+    /**
+     * Renumbers the identities of every live piece from zero, once the newest piece's identity
+     * is about to overflow.  Pieces are numbered in the order they are found, so cells that
+     * shared an identity before still share one afterwards.  Generators that don't give pieces
+     * identities never come close to overflowing, so this only ever applies to the ones that do.
+     */
+    fn compact_identities_if_needed(&mut self) {
+        let core = &mut self.core;
+        let newest = core.get_next_tetrimino(core.get_tetrimino_queue_length() - 1);
+        if get_identity(newest.values[0]) < MAX_IDENTITY - IDENTITY_COMPACTION_MARGIN {
+            return
+        }
+
+        let mut identities = HashMap::new();
+        core.map_cell_values(|value| {
+            if value == GARBAGE_VALUE {
+                return value
+            }
+
+            let next_identity = identities.len() as u32;
+            let identity = *identities.entry(get_identity(value)).or_insert(next_identity);
+            with_identity(value, identity)
+        });
+
+        core.set_next_identity(identities.len() as u32);
+    }
+
+    fn check_invariants(&mut self, filled_cells: usize, deleted_rows: &Option<Vec<i32>>, deleted_points: &Option<Vec<Point>>, chain_transition: &BoardTransition) {
+        let board_violations = self.core.get_board_mut().take_violations();
+        self.invariant_violations.extend(board_violations);
+
+        let board = self.core.get_board();
+        self.invariant_violations.extend(board.find_inconsistencies());
+
+        // falling points only move, so the only cells that should go missing are the cleared ones
+        let num_cleared = deleted_rows.as_ref().map_or(0, |rows| rows.len() * board.get_width())
+            + deleted_points.as_ref().map_or(0, |points| points.len());
+        let remaining_cells = board.num_filled_cells();
+        if remaining_cells + num_cleared < filled_cells {
+            self.invariant_violations.push(format!(
+                "{} cells were lost, {} filled before and {} cleared, but only {} remain",
+                filled_cells - num_cleared - remaining_cells, filled_cells, num_cleared, remaining_cells));
+        }
+
+        if chain_transition.is_inert() {
+            let floating = self.clear_physics.find_floating_points(board);
+            if !floating.is_empty() {
+                self.invariant_violations.push(format!("the board settled with floating points {:?}", floating));
+            }
+        }
+
+        self.invariant_violations.truncate(MAX_INVARIANT_VIOLATIONS);
+    }
+
+    /**
+     * Adds another piece for another player to control, which spawns offset from the usual spawn point.
+     */
+    pub fn add_piece(&mut self, spawn_offset: Point) {
+        self.core.add_piece(spawn_offset);
+        self.parked_timers.push(Some(PieceTimers {
+            can_hold: true,
+            ..Default::default()
+        }));
+    }
+
+    /**
+     * Selects the piece that every following control and frame of gravity applies to.
+     */
+    pub fn select_piece(&mut self, index: usize) {
+        let selected = self.core.get_selected_piece();
+        if index == selected {
+            return
+        }
+
+        let timers = self.parked_timers[index].take().unwrap();
+        let parked = PieceTimers {
+            frames_since_drop: self.frames_since_drop,
+            frames_since_lock_delay: self.frames_since_lock_delay,
+            lock_delayed: self.lock_delayed,
+            fastfalling: self.fastfalling,
+            frames_until_spawn: self.frames_until_spawn,
+            can_hold: self.can_hold,
+        };
+        self.parked_timers[selected] = Some(parked);
+
+        self.frames_since_drop = timers.frames_since_drop;
+        self.frames_since_lock_delay = timers.frames_since_lock_delay;
+        self.lock_delayed = timers.lock_delayed;
+        self.fastfalling = timers.fastfalling;
+        self.frames_until_spawn = timers.frames_until_spawn;
+        self.can_hold = timers.can_hold;
+
+        self.core.select_piece(index);
+    }
+
+    pub fn get_rng_seed(&self) -> &[u8] {
+        &self.rng_seed
+    }
+
+    /**
+     * Starts keeping the result of every placement, for whatever turns them into attacks.
+     */
+    pub fn collect_lock_results(&mut self) {
+        self.lock_results.get_or_insert_with(Vec::new);
+    }
+
+    pub fn take_lock_results(&mut self) -> Vec<LockResult> {
+        self.lock_results.as_mut().map_or(Vec::new(), std::mem::take)
+    }
+
+    pub fn take_invariant_violations(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.invariant_violations)
+    }
+
+    fn save_state(&self) -> DriverCoreState {
+        DriverCoreState {
+            core: self.core.save_state(),
+            frames_since_drop: self.frames_since_drop,
+            level: self.level,
+            score: self.score,
+            lines_cleared: self.lines_cleared,
+            garbage_lines_cleared: self.garbage_lines_cleared,
+            frames_elapsed: self.frames_elapsed,
+            game_result: self.game_result,
+            last_clear_frame: self.last_clear_frame,
+            frames_since_lock_delay: self.frames_since_lock_delay,
+            lock_delayed: self.lock_delayed,
+            fastfalling: self.fastfalling,
+            frames_until_spawn: self.frames_until_spawn,
+            parked_timers: self.parked_timers.clone(),
+            can_hold: self.can_hold,
+        }
+    }
+
+    fn load_state(&mut self, state: &DriverCoreState) {
+        self.core.load_state(&state.core);
+        self.frames_since_drop = state.frames_since_drop;
+        self.level = state.level;
+        self.score = state.score;
+        self.lines_cleared = state.lines_cleared;
+        self.garbage_lines_cleared = state.garbage_lines_cleared;
+        self.frames_elapsed = state.frames_elapsed;
+        self.game_result = state.game_result;
+        self.last_clear_frame = state.last_clear_frame;
+        self.frames_since_lock_delay = state.frames_since_lock_delay;
+        self.lock_delayed = state.lock_delayed;
+        self.fastfalling = state.fastfalling;
+        self.frames_until_spawn = state.frames_until_spawn;
+        self.parked_timers = state.parked_timers.clone();
+        self.can_hold = state.can_hold;
+    }
 }
 
+#[derive(Clone)]
 pub struct BasicGenerator {
     tetrimino_chooser: utils::tetrimino_chooser::TetriminoChooser
 }
@@ -261,6 +815,10 @@ impl BasicGenerator {
 }
 
 impl TetriminoGenerator for BasicGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
     fn next(&mut self) -> Tetrimino {
         let (index, tetrimino_type) = self.tetrimino_chooser.choose_tetrimino_type();
         let values = vec![index as u32; 4];
@@ -274,6 +832,18 @@ impl TetriminoGenerator for BasicGenerator {
     fn set_seed(&mut self, seed: Vec<u8>) {
         self.tetrimino_chooser.set_seed(seed);
     }
+
+    fn start_bag_without(&mut self, drawn: &[usize]) {
+        self.tetrimino_chooser.start_bag_without(drawn);
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        self.tetrimino_chooser.save_state()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        self.tetrimino_chooser.load_state(state);
+    }
 }
 
 pub trait BuildableDriver {
@@ -292,8 +862,13 @@ pub struct DriverBuilder<T: BuildableDriver> {
     queue_length: usize,
     lock_delay: usize,
     get_gravity: fn(usize, bool) -> f32,
+    time_limit: Option<usize>,
+    stack_visibility: StackVisibility,
+    piece_scale: i32,
+    movement_step: i32,
     rng_seed: Vec<u8>,
     tetrimino_generator: Option<Box<dyn TetriminoGenerator>>,
+    clear_physics: Option<Box<dyn ClearPhysics>>,
 
     cont: T::Data
 }
@@ -306,8 +881,13 @@ impl<T: BuildableDriver> DriverBuilder<T> {
             queue_length: defaults::settings::QUEUE_LENGTH,
             lock_delay: 120,
             get_gravity: defaults::gravity::calculate_gravity,
-            rng_seed: (0..32).map(|_| thread_rng().gen::<u8>()).collect(),
+            time_limit: None,
+            stack_visibility: StackVisibility::visible(),
+            piece_scale: 1,
+            movement_step: 1,
+            rng_seed: utils::tetrimino_chooser::generate_rng_seed(),
             tetrimino_generator: None,
+            clear_physics: None,
 
             cont: Default::default()
         })
@@ -322,7 +902,8 @@ impl<T: BuildableDriver> DriverBuilder<T> {
     }
 
     pub fn build_core(&mut self) -> DriverCore {
-        let board = Board::new(self.width, self.height);
+        let scale = self.piece_scale as usize;
+        let board = Board::new(self.width * scale, self.height * scale);
 
         let mut tetrimino_generator = self.tetrimino_generator
             .take()
@@ -334,7 +915,8 @@ impl<T: BuildableDriver> DriverBuilder<T> {
         let core = GameCore::new(
             board,
             self.queue_length,
-            tetrimino_generator);
+            tetrimino_generator)
+            .with_piece_scale(self.piece_scale, self.movement_step);
 
         DriverCore {
             core,
@@ -344,12 +926,34 @@ impl<T: BuildableDriver> DriverBuilder<T> {
 
             level: 0,
             score: 0,
+            lines_cleared: 0,
+            garbage_lines_cleared: 0,
+
+            frames_elapsed: 0,
+            time_limit: self.time_limit,
+            game_result: None,
+
+            stack_visibility: self.stack_visibility,
+            last_clear_frame: None,
 
             lock_delay: self.lock_delay,
             frames_since_lock_delay: 0,
             lock_delayed: false,
             fastfalling: false,
 
+            spawn_delay: 0,
+            frames_until_spawn: 0,
+
+            clear_physics: self.clear_physics
+                .take()
+                .unwrap_or(NaivePhysics::new()),
+
+            invariant_violations: Vec::new(),
+            lock_results: None,
+            parked_timers: vec![None],
+
+            rng_seed: self.rng_seed.clone(),
+
             can_hold: true,
         }
     }
@@ -363,12 +967,46 @@ impl<T: BuildableDriver> DriverBuilder<T> {
         self
     }
 
+    pub fn with_clear_physics(mut self, clear_physics: Box<dyn ClearPhysics>) -> Self {
+        self.clear_physics = Some(clear_physics);
+        self
+    }
+
+    pub fn with_time_limit(mut self, time_limit: usize) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_stack_visibility(mut self, stack_visibility: StackVisibility) -> Self {
+        self.stack_visibility = stack_visibility;
+        self
+    }
+
+    /**
+     * Doubles the size of every tetrimino, along with the width and height of the board so that
+     * it holds as many of them.  Big tetriminos move and fall two cells at a time, unless half
+     * steps are enabled.
+     */
+    pub fn with_big_tetriminos(mut self, half_step: bool) -> Self {
+        self.piece_scale = 2;
+        self.movement_step = if half_step { 1 } else { 2 };
+        self
+    }
+
     pub fn with_rng_seed(mut self, rng_seed: Vec<u8>) -> Self {
         self.rng_seed = rng_seed;
         self
     }
 
-    pub fn _with_width(mut self, width: usize) -> Self {
+    /**
+     * Creates a random number generator from the builder's seed, for drivers that need
+     * randomness outside of the tetrimino generator (such as generating garbage).
+     */
+    pub fn get_seeded_rng(&self) -> SeededRng {
+        utils::tetrimino_chooser::rng_from_seed(&self.rng_seed)
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
         self.width = width;
         self
     }
@@ -388,8 +1026,146 @@ impl<T: BuildableDriver> DriverBuilder<T> {
         self
     }
 
-    pub fn _with_get_gravity(mut self, get_gravity: fn(usize, bool) -> f32) -> Self {
+    pub fn with_get_gravity(mut self, get_gravity: fn(usize, bool) -> f32) -> Self {
         self.get_gravity = get_gravity;
         self
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use classic_driver::ClassicDriver;
+
+    fn place_pieces(driver: &mut dyn Driver, num_pieces: usize) {
+        for i in 0..num_pieces {
+            if i % 3 == 0 {
+                driver.hold();
+            }
+            driver.translate_left();
+
+            let mut transition = driver.fastfall();
+            while !transition.is_inert() {
+                transition = driver.finish_transition(transition);
+            }
+        }
+    }
+
+    #[test]
+    fn loaded_state_plays_out_the_same() {
+        let mut driver = DriverBuilder::<ClassicDriver>::new().with_rng_seed(vec![1]).build();
+        place_pieces(&mut driver, 10);
+
+        let state = serde_json::to_string(&driver.save_state()).unwrap();
+        let mut copy = DriverBuilder::<ClassicDriver>::new().with_rng_seed(vec![2]).build();
+        copy.load_state(&serde_json::from_str(&state).unwrap());
+
+        place_pieces(&mut driver, 20);
+        place_pieces(&mut copy, 20);
+        assert_eq!(copy.get_game_core().get_board().get_cells(), driver.get_game_core().get_board().get_cells());
+        assert_eq!(copy.get_score(), driver.get_score());
+    }
+
+    #[test]
+    fn big_tetriminos_fall_onto_their_ghost_in_whole_steps() {
+        // full big tetriminos stay on the 2x2 grid, so they stop short of a floor one cell high
+        for (half_step, expected_bottom) in [(false, 2), (true, 1)] {
+            let mut driver = DriverBuilder::<ClassicDriver>::new().with_big_tetriminos(half_step).build();
+            assert_eq!(driver.get_game_core().get_board().get_width(), 2 * defaults::dimensions::CELL_WIDTH);
+
+            let board = driver.get_game_core_mut().get_board_mut();
+            for x in 0..board.get_width() as i32 {
+                board.fill_point(Point(x, 0), GARBAGE_VALUE);
+            }
+            driver.get_game_core_mut().active_tetrimino_updated();
+
+            let ghost = driver.get_game_core().get_piece(0).get_ghost_tetrimino().clone();
+            assert_eq!(ghost.iter().map(|p| p.y()).min(), Some(expected_bottom));
+
+            while driver.get_game_core_mut().try_fall() {}
+            assert_eq!(driver.get_game_core().get_active_tetrimino().get_points(), ghost);
+        }
+    }
+
+    #[test]
+    fn t_spins_need_three_filled_corners() {
+        for overhang in [false, true] {
+            let mut driver = DriverBuilder::<ClassicDriver>::new().build();
+            let core = driver.get_game_core_mut();
+
+            // a t tetrimino pointing down into a slot at the bottom of the board
+            let mut t_tetrimino = defaults::tetriminos::T_TETRIMINO.instance(vec![1; 4]).as_active_instance(Point(4, 4));
+            let lowest_points = |t: &ActiveTetrimino| {
+                let bottom = t.get_points().iter().map(|p| p.y()).min().unwrap();
+                t.get_points().into_iter().filter(|p| p.y() == bottom).collect::<Vec<_>>()
+            };
+            while lowest_points(&t_tetrimino).len() != 1 {
+                t_tetrimino.rotate(Direction::Clockwise);
+            }
+            let stem = lowest_points(&t_tetrimino)[0];
+            t_tetrimino.translate(Point(0, -stem.y()));
+            let stem = stem - Point(0, stem.y());
+
+            let slot = t_tetrimino.get_points();
+            let board = core.get_board_mut();
+            for y in 0..2 {
+                for x in 0..board.get_width() as i32 {
+                    if !slot.contains(&Point(x, y)) {
+                        board.fill_point(Point(x, y), 1);
+                    }
+                }
+            }
+            if overhang {
+                board.fill_point(stem + Point(-1, 2), 1);
+            }
+
+            // the t tetrimino is rotated into the slot
+            let mut unrotated = t_tetrimino.clone();
+            unrotated.rotate(Direction::CounterClockwise);
+            core.set_active_tetrimino(unrotated);
+            assert!(core.rotate(Direction::Clockwise));
+            assert_eq!(core.get_active_tetrimino().get_points(), slot);
+
+            assert_eq!(core.add_tetrimino().map(|rows| rows.len()), Some(2));
+            assert_eq!(core.get_last_placement().t_spin, overhang);
+        }
+    }
+
+    #[test]
+    fn garbage_rises_all_at_once_or_not_at_all() {
+        let mut driver = DriverBuilder::<ClassicDriver>::new().with_rng_seed(vec![1]).build();
+        let core = driver.get_game_core_mut();
+        let height = core.get_board().get_height();
+
+        // the active tetrimino spawns near the top of the board, so it can't be carried up far
+        let spawn_position = core.get_active_tetrimino().position;
+        let top = core.get_active_tetrimino().get_points().iter().map(|p| p.y()).max().unwrap();
+        let rows_over_the_top = vec![vec![0]; height - top as usize];
+        assert!(!core.raise_garbage_rows(&rows_over_the_top));
+        assert_eq!(core.get_board().num_active_rows(), 0);
+        assert_eq!(core.get_active_tetrimino().position, spawn_position);
+
+        for _ in 0..5 {
+            core.try_fall();
+        }
+        assert!(core.raise_garbage_rows(&[vec![0], vec![1]]));
+        assert_eq!(core.get_board().num_active_rows(), 2);
+        assert_eq!(core.get_active_tetrimino().position, spawn_position - Point(0, 3));
+
+        let too_many_rows = vec![vec![0]; height];
+        assert!(!core.raise_garbage_rows(&too_many_rows));
+        assert_eq!(core.get_board().num_active_rows(), 2);
+        assert_eq!(core.get_active_tetrimino().position, spawn_position - Point(0, 3));
+    }
+
+    #[test]
+    fn lock_results_are_only_kept_once_collected() {
+        let mut driver = DriverBuilder::<ClassicDriver>::new().build();
+        place_pieces(&mut driver, 3);
+        assert!(driver.get_driver_core_mut().take_lock_results().is_empty());
+
+        driver.get_driver_core_mut().collect_lock_results();
+        place_pieces(&mut driver, 3);
+        assert_eq!(driver.get_driver_core_mut().take_lock_results().len(), 3);
+    }
+}
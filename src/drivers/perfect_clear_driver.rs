@@ -0,0 +1,243 @@
+use crate::drivers::*;
+use super::utils::scoring::ClassicScoring;
+
+
+/**
+ * A starting position to practice perfect clears from.
+ */
+#[derive(Clone, Copy)]
+pub struct PerfectClearSetup {
+    pub name: &'static str,
+    // each row of the prefilled field from the top down, where each letter is the tetrimino that filled the cell
+    pub rows: &'static [&'static str],
+    // the tetriminos already drawn from the current bag, which only come up again in the next bag
+    pub drawn: &'static str,
+    // the number of tetriminos allowed to clear the board with
+    pub piece_limit: usize,
+}
+
+pub const PERFECT_CLEAR_SETUPS: &[PerfectClearSetup] = &[
+    PerfectClearSetup {
+        name: "first",
+        rows: &[],
+        drawn: "",
+        piece_limit: 10,
+    },
+    PerfectClearSetup {
+        name: "opener",
+        rows: &[
+            "SIIIIZ....",
+            "SSOOZZ....",
+            "LSOOZJ....",
+            "LLLJJJ....",
+        ],
+        drawn: "IOSZLJ",
+        piece_limit: 4,
+    },
+    PerfectClearSetup {
+        name: "second",
+        rows: &[],
+        drawn: "ITO",
+        piece_limit: 10,
+    },
+];
+
+/**
+ * Repeatedly sets up the board and asks for a perfect clear within a limited number of
+ * tetriminos, moving on to the next setup whether or not it succeeds.
+ */
+pub struct PerfectClearDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+    setups: &'static [PerfectClearSetup],
+    current_setup: usize,
+    pieces_placed: usize,
+    attempts: usize,
+    successes: usize,
+}
+
+pub struct PerfectClearDriverBuilderData {
+    setups: &'static [PerfectClearSetup],
+}
+
+impl Default for PerfectClearDriverBuilderData {
+    fn default() -> Self {
+        Self {
+            setups: PERFECT_CLEAR_SETUPS
+        }
+    }
+}
+
+impl PerfectClearDriver {
+    fn cell_value(c: char) -> Option<u32> {
+        defaults::tetriminos::TETRIMINO_NAMES.find(c).map(|index| index as u32)
+    }
+
+    /**
+     * Resets the board to the given setup, along with a fresh queue drawn from what is left of the setup's bag.
+     */
+    fn load_setup(&mut self, index: usize) {
+        self.current_setup = index;
+        self.pieces_placed = 0;
+
+        let setup = self.setups[index];
+        let board = self.driver_core.core.get_board_mut();
+        board.clear();
+        for (y, row) in setup.rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if let Some(value) = Self::cell_value(c) {
+                    board.fill_point(Point(x as i32, y as i32), value);
+                }
+            }
+        }
+
+        let drawn = setup.drawn.chars().filter_map(Self::cell_value).map(|value| value as usize).collect::<Vec<_>>();
+        self.driver_core.core.restart_queue(&drawn);
+        self.driver_core.can_hold = true;
+    }
+
+    /**
+     * Records the result of the current attempt, and moves on to the next setup.
+     */
+    fn finish_attempt(&mut self, success: bool) {
+        self.attempts += 1;
+        if success {
+            self.successes += 1;
+        }
+
+        self.load_setup((self.current_setup + 1) % self.setups.len());
+    }
+}
+
+impl BuildableDriver for PerfectClearDriver {
+    type Data = PerfectClearDriverBuilderData;
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        let mut driver = Self {
+            scoring: ClassicScoring::new(),
+            driver_core: builder.build_core(),
+            setups: builder.cont.setups,
+            current_setup: 0,
+            pieces_placed: 0,
+            attempts: 0,
+            successes: 0,
+        };
+
+        driver.load_setup(0);
+        driver
+    }
+}
+
+impl DriverBuilder<PerfectClearDriver> {
+    pub fn _with_setups(mut self, setups: &'static [PerfectClearSetup]) -> Self {
+        self.cont.setups = setups;
+        self
+    }
+}
+
+impl Driver for PerfectClearDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        let setup = self.setups[self.current_setup];
+        let success_rate = (100 * self.successes).checked_div(self.attempts).unwrap_or(0);
+
+        vec![
+            format!("setup: {}", setup.name),
+            format!("pieces: {} / {}", self.pieces_placed, setup.piece_limit),
+            format!("perfect clears: {} / {}", self.successes, self.attempts),
+            format!("success rate: {}%", success_rate),
+        ]
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        if transition.get_points_added().is_some() {
+            self.pieces_placed += 1;
+        }
+
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition);
+        if let Some(rows) = cleared_rows {
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+        }
+
+        // wait for the board to settle before judging the attempt
+        if new_transition.is_inert() && !self.is_game_over() {
+            let board_empty = self.driver_core.core.get_board().num_active_rows() == 0;
+            if board_empty && self.pieces_placed > 0 {
+                self.finish_attempt(true);
+            } else if self.pieces_placed >= self.setups[self.current_setup].piece_limit {
+                self.finish_attempt(false);
+            }
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.scoring, &self.current_setup, &self.pieces_placed, &self.attempts, &self.successes))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((scoring, current_setup, pieces_placed, attempts, successes)) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+            self.current_setup = current_setup;
+            self.pieces_placed = pieces_placed;
+            self.attempts = attempts;
+            self.successes = successes;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_core::utils::orientations::Direction;
+
+    fn normalized(points: Vec<Point>) -> Vec<Point> {
+        let min = Point(points.iter().map(|p| p.x()).min().unwrap(), points.iter().map(|p| p.y()).min().unwrap());
+        let mut points = points.into_iter().map(|p| p - min).collect::<Vec<_>>();
+        points.sort_by_key(|p| (p.y(), p.x()));
+        points
+    }
+
+    #[test]
+    fn setups_are_built_from_whole_tetriminos() {
+        for setup in PERFECT_CLEAR_SETUPS.iter() {
+            // setups may also have drawn tetriminos that were used up by an earlier clear
+            for c in setup.drawn.chars().filter(|&c| setup.rows.iter().any(|row| row.contains(c))) {
+                let points = setup.rows
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .flat_map(|(y, row)| row.chars()
+                        .enumerate()
+                        .filter(move |&(_, cell)| cell == c)
+                        .map(move |(x, _)| Point(x as i32, y as i32)))
+                    .collect::<Vec<_>>();
+
+                let index = PerfectClearDriver::cell_value(c).unwrap() as usize;
+                let mut tetrimino = defaults::tetriminos::TETRIMINOS[index].instance(vec![0; 4]).as_active_instance(Point(0, 0));
+                let fits = (0..4).any(|_| {
+                    tetrimino.rotate(Direction::Clockwise);
+                    normalized(tetrimino.get_points()) == normalized(points.clone())
+                });
+                assert!(fits, "{} in the {} setup", c, setup.name);
+            }
+        }
+    }
+
+    #[test]
+    fn opener_leaves_only_a_t_in_the_bag() {
+        let mut driver = DriverBuilder::<PerfectClearDriver>::new().build();
+        driver.load_setup(1);
+
+        let t_points = defaults::tetriminos::T_TETRIMINO.get_points();
+        assert_eq!(driver.get_game_core().get_active_tetrimino().get_tetrimino().tetrimino_type.get_points(), t_points);
+    }
+}
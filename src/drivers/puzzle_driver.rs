@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::drivers::*;
+use crate::game_core::defaults;
+use crate::game_core::defaults::tetriminos::TETRIMINO_NAMES;
+use super::utils::scoring::ClassicScoring;
+
+
+const PUZZLE_DIRECTORY: &str = "./res/puzzles";
+
+/**
+ * What the player must achieve with the puzzle's pieces.
+ */
+#[derive(Clone, Copy, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    ClearLines { lines: usize },
+    TSpinDouble,
+    NoHoles,
+    PerfectClear,
+}
+
+impl Objective {
+    fn describe(&self) -> String {
+        match self {
+            Objective::ClearLines { lines } => format!("clear {} lines", lines),
+            Objective::TSpinDouble => "perform a t-spin double".to_string(),
+            Objective::NoHoles => "leave no holes".to_string(),
+            Objective::PerfectClear => "perfect clear".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    // each row of the starting field from the top down, where each letter is the tetrimino that filled the cell
+    #[serde(default)]
+    pub rows: Vec<String>,
+    // the tetriminos that will be played, in order
+    pub pieces: String,
+    pub objective: Objective,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl Puzzle {
+    /**
+     * A puzzle needs at least one piece to play, and every piece must name a tetrimino.
+     */
+    fn check_pieces(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            Err(format!("puzzle \"{}\" has no pieces", self.name))
+        } else if let Some(c) = self.pieces.chars().find(|&c| tetrimino_index(c).is_none()) {
+            Err(format!("puzzle \"{}\" has an unknown piece '{}'", self.name, c))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl PuzzlePack {
+    fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let pack: Self = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+        for puzzle in pack.puzzles.iter() {
+            puzzle.check_pieces()?;
+        }
+
+        Ok(pack)
+    }
+
+    /**
+     * Loads every puzzle pack in the puzzle directory, sorted by name.  Packs that can't be
+     * read are skipped and reported.
+     */
+    pub fn load_all() -> Vec<Self> {
+        let entries = match fs::read_dir(PUZZLE_DIRECTORY) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("could not read the puzzle directory {}: {}", PUZZLE_DIRECTORY, e);
+                return Vec::new()
+            }
+        };
+
+        let mut packs = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| !path.is_dir())
+            .filter_map(|path| match Self::load(&path) {
+                Ok(pack) => Some(pack),
+                Err(e) => {
+                    eprintln!("skipping the puzzle pack {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        packs
+    }
+}
+
+fn tetrimino_index(c: char) -> Option<usize> {
+    TETRIMINO_NAMES.find(c)
+}
+
+/**
+ * Plays a fixed sequence of tetriminos, starting over once it runs out.
+ */
+#[derive(Clone)]
+pub struct SequenceGenerator {
+    tetrimino_types: &'static [TetriminoType],
+    sequence: Vec<usize>,
+    current_index: usize,
+}
+
+impl SequenceGenerator {
+    pub fn new(tetrimino_types: &'static [TetriminoType], sequence: Vec<usize>) -> Box<Self> {
+        Box::new(Self {
+            tetrimino_types,
+            sequence,
+            current_index: 0,
+        })
+    }
+}
+
+impl TetriminoGenerator for SequenceGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
+    fn next(&mut self) -> Tetrimino {
+        let index = self.sequence[self.current_index % self.sequence.len()];
+        self.current_index += 1;
+        self.tetrimino_types[index].instance(vec![index as u32; 4])
+    }
+
+    fn get_tetrimino_types(&self) -> &'static [TetriminoType] {
+        self.tetrimino_types
+    }
+
+    fn set_seed(&mut self, _: Vec<u8>) {}
+
+    fn save_state(&self) -> serde_json::Value {
+        self.current_index.into()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        if let Some(current_index) = state.as_u64() {
+            self.current_index = current_index as usize;
+        }
+    }
+}
+
+/**
+ * Plays a single puzzle, checking its objective after every placed tetrimino.  The game
+ * is completed once the objective is met, and failed once the pieces run out.
+ */
+pub struct PuzzleDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+    puzzle: Puzzle,
+    pieces_placed: usize,
+    performed_t_spin_double: bool,
+}
+
+#[derive(Default)]
+pub struct PuzzleDriverBuilderData {
+    puzzle: Option<Puzzle>,
+}
+
+impl PuzzleDriver {
+    fn has_holes(&self) -> bool {
+        let board = self.driver_core.core.get_board();
+        (0..board.get_width() as i32).any(|x| {
+            let mut filled_above = false;
+            (0..board.num_active_rows() as i32).rev().any(|y| {
+                let filled = board.is_point_filled(Point(x, y));
+                let is_hole = filled_above && !filled;
+                filled_above |= filled;
+                is_hole
+            })
+        })
+    }
+
+    fn is_objective_met(&self) -> bool {
+        match self.puzzle.objective {
+            Objective::ClearLines { lines } => self.driver_core.lines_cleared >= lines,
+            Objective::TSpinDouble => self.performed_t_spin_double,
+            Objective::NoHoles => self.pieces_placed == self.puzzle.pieces.len() && !self.has_holes(),
+            Objective::PerfectClear => self.driver_core.core.get_board().num_active_rows() == 0,
+        }
+    }
+}
+
+impl BuildableDriver for PuzzleDriver {
+    type Data = PuzzleDriverBuilderData;
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        let puzzle = builder.cont.puzzle.take().unwrap();
+        let mut driver_core = builder.build_core();
+
+        let board = driver_core.core.get_board_mut();
+        for (y, row) in puzzle.rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if let Some(index) = tetrimino_index(c) {
+                    board.fill_point(Point(x as i32, y as i32), index as u32);
+                }
+            }
+        }
+        driver_core.core.active_tetrimino_updated();
+
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core,
+            puzzle,
+            pieces_placed: 0,
+            performed_t_spin_double: false,
+        }
+    }
+}
+
+impl DriverBuilder<PuzzleDriver> {
+    pub fn with_puzzle(mut self, puzzle: Puzzle) -> Self {
+        let sequence = puzzle.pieces.chars().filter_map(tetrimino_index).collect();
+        self.cont.puzzle = Some(puzzle);
+        self.with_tetrimino_generator(SequenceGenerator::new(defaults::tetriminos::TETRIMINOS, sequence))
+    }
+}
+
+impl Driver for PuzzleDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        vec![
+            self.puzzle.name.clone(),
+            self.puzzle.objective.describe(),
+            format!("pieces: {} / {}", self.pieces_placed, self.puzzle.pieces.len()),
+        ]
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let placed = transition.get_points_added().is_some();
+        if placed {
+            self.pieces_placed += 1;
+        }
+
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition);
+        if let Some(rows) = cleared_rows {
+            if placed && rows.len() == 2 && self.driver_core.core.get_last_placement().t_spin {
+                self.performed_t_spin_double = true;
+            }
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+        }
+
+        // the objective is checked once the board has settled after each placed tetrimino
+        if new_transition.is_inert() && self.pieces_placed > 0 {
+            if self.is_objective_met() {
+                self.driver_core.end_game(GameResult::Completed);
+            } else if self.pieces_placed >= self.puzzle.pieces.len() {
+                self.driver_core.end_game(GameResult::Failed);
+            }
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.scoring, &self.pieces_placed, &self.performed_t_spin_double))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((scoring, pieces_placed, performed_t_spin_double)) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+            self.pieces_placed = pieces_placed;
+            self.performed_t_spin_double = performed_t_spin_double;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzles_without_playable_pieces_are_rejected() {
+        let path = std::env::temp_dir().join("tetrust_empty_puzzle.json");
+        for pieces in ["", "TX"] {
+            let contents = format!(r#"{{"name": "pack", "puzzles": [{{"name": "bad", "pieces": "{}", "objective": {{"type": "no_holes"}}}}]}}"#, pieces);
+            fs::write(&path, contents).unwrap();
+            assert!(PuzzlePack::load(&path).is_err());
+        }
+        let _ = fs::remove_file(&path);
+
+        assert!(!PuzzlePack::load_all().is_empty());
+    }
+}
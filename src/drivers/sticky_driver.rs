@@ -7,6 +7,7 @@ use super::utils::recursive_physics::*;
 use super::utils::tetrimino_chooser::TetriminoChooser;
 
 
+#[derive(Clone)]
 pub struct StickyGenerator {
     tetrimino_chooser: TetriminoChooser,
 }
@@ -20,6 +21,10 @@ impl StickyGenerator {
 }
 
 impl TetriminoGenerator for StickyGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator> {
+        Box::new(self.clone())
+    }
+
     fn next(&mut self) -> Tetrimino {
         let (index, tetrimino_type) = self.tetrimino_chooser.choose_tetrimino_type();
         let mut values = vec![index as u32; 4];
@@ -45,6 +50,14 @@ impl TetriminoGenerator for StickyGenerator {
     fn set_seed(&mut self, seed: Vec<u8>) {
         self.tetrimino_chooser.set_seed(seed);
     }
+
+    fn save_state(&self) -> serde_json::Value {
+        self.tetrimino_chooser.save_state()
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value) {
+        self.tetrimino_chooser.load_state(state);
+    }
 }
 
 pub struct StickyDriver {
@@ -58,6 +71,7 @@ impl BuildableDriver for StickyDriver {
         builder
             .with_tetrimino_generator(
                 StickyGenerator::new(defaults::tetriminos::TETRIMINOS))
+            .with_clear_physics(StickyPhysics::new())
     }
  
     fn build(mut builder: DriverBuilder<Self>) -> Self {
@@ -104,15 +118,14 @@ impl Driver for StickyDriver {
     }
 
     fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
-        let (cleared_rows, _, mut new_transitions) = self.driver_core.finish_transition(transition);
-
-        if let Some(rows) = cleared_rows {
-            let falls = calculate_sticky_falls_from_rows(self.driver_core.core.get_board(), rows);
-            if !falls.is_empty() {
-                new_transitions.add_points_falling(falls)
-            }
-        }
-
+        let (_, _, new_transitions) = self.driver_core.finish_transition(transition);
         new_transitions
     }
+
+    // everything this mode keeps track of is in its core
+    fn get_mode_state(&self) -> ModeState {
+        ModeState::Null
+    }
+
+    fn set_mode_state(&mut self, _: &ModeState) {}
 }
\ No newline at end of file
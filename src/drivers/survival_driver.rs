@@ -0,0 +1,128 @@
+use rand::Rng;
+
+use crate::drivers::*;
+use crate::game_core::defaults::settings::FRAMES_PER_SECOND;
+
+
+// garbage rises every 10 seconds at first, speeding up by half a second each level
+const STARTING_RISE_INTERVAL: usize = 10 * FRAMES_PER_SECOND;
+const MINIMUM_RISE_INTERVAL: usize = 2 * FRAMES_PER_SECOND;
+const RISE_INTERVAL_STEP: usize = FRAMES_PER_SECOND / 2;
+// the level increases every 30 seconds
+const FRAMES_PER_LEVEL: usize = 30 * FRAMES_PER_SECOND;
+const MAX_LEVEL: usize = 14;
+
+/**
+ * Garbage rows rise up from beneath the stack on a timer that speeds up over
+ * time, and the player survives for as long as they can.
+ */
+pub struct SurvivalDriver {
+    driver_core: DriverCore,
+    rng: SeededRng,
+    frames_until_rise: usize,
+    last_hole: Option<i32>,
+}
+
+impl SurvivalDriver {
+    fn get_rise_interval(level: usize) -> usize {
+        std::cmp::max(MINIMUM_RISE_INTERVAL, STARTING_RISE_INTERVAL.saturating_sub(level * RISE_INTERVAL_STEP))
+    }
+
+    /**
+     * Pushes a new garbage row in beneath the stack, with a hole that does not line up
+     * with the previous one.
+     */
+    fn raise_garbage(&mut self) -> BoardTransition {
+        let width = self.driver_core.core.get_board().get_width() as i32;
+        let mut hole = self.rng.gen_range(0..width);
+        while Some(hole) == self.last_hole {
+            hole = self.rng.gen_range(0..width);
+        }
+        self.last_hole = Some(hole);
+
+        if !self.driver_core.core.raise_garbage_rows(&[vec![hole]]) {
+            self.driver_core.end_game(GameResult::ToppedOut);
+            return BoardTransition::new();
+        }
+
+        let mut transition = BoardTransition::new();
+        transition.add_rows_risen(1);
+        transition
+    }
+
+    /**
+     * The score is the number of seconds survived, plus the number of lines cleared.
+     */
+    fn update_score(&mut self) {
+        let frames_elapsed = self.driver_core.frames_elapsed;
+        self.driver_core.score = frames_elapsed / FRAMES_PER_SECOND + self.driver_core.lines_cleared;
+        self.driver_core.level = std::cmp::min(frames_elapsed / FRAMES_PER_LEVEL, MAX_LEVEL);
+    }
+}
+
+impl BuildableDriver for SurvivalDriver {
+    type Data = ();
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        Self {
+            rng: builder.get_seeded_rng(),
+            driver_core: builder.build_core(),
+            frames_until_rise: Self::get_rise_interval(0),
+            last_hole: None,
+        }
+    }
+}
+
+impl Driver for SurvivalDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn next_frame(&mut self) -> BoardTransition {
+        let transition = if self.driver_core.process_frame() {
+            self.fall()
+        } else {
+            BoardTransition::new()
+        };
+
+        if self.is_game_over() {
+            return transition;
+        }
+
+        self.update_score();
+        if self.frames_until_rise > 0 {
+            self.frames_until_rise -= 1;
+        }
+
+        // garbage only rises while nothing else is happening on the board
+        if self.frames_until_rise == 0 && transition.is_inert() {
+            self.frames_until_rise = Self::get_rise_interval(self.driver_core.level);
+            self.raise_garbage()
+        } else {
+            transition
+        }
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let (_, _, new_transition) = self.driver_core.finish_transition(transition); 
+        self.update_score();
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.rng, &self.frames_until_rise, &self.last_hole))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((rng, frames_until_rise, last_hole)) = from_mode_state(mode_state) {
+            self.rng = rng;
+            self.frames_until_rise = frames_until_rise;
+            self.last_hole = last_hole;
+        }
+    }
+}
\ No newline at end of file
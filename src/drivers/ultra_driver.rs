@@ -0,0 +1,59 @@
+use crate::drivers::*;
+use crate::game_core::defaults;
+use super::utils::scoring::ClassicScoring;
+
+
+/**
+ * A score attack mode, played with the classic rules until the time limit
+ * runs out.
+ */
+pub struct UltraDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+}
+
+impl BuildableDriver for UltraDriver {
+    type Data = ();
+
+    fn initialize(builder: DriverBuilder<Self>) -> DriverBuilder<Self> {
+        builder
+            .with_time_limit(defaults::settings::ULTRA_TIME_LIMIT)
+    }
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core: builder.build_core()
+        }
+    }
+}
+
+impl Driver for UltraDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition); 
+
+        if let Some(rows) = cleared_rows {
+            self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state(&self.scoring)
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some(scoring) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+        }
+    }
+}
\ No newline at end of file
@@ -1,12 +1,16 @@
+use serde::{Serialize, Deserialize};
+
 use crate::game_core::utils::point::*;
 
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct BoardTransition {
     points_deleted: Vec<Point>,
     rows_deleted: Vec<i32>,
     points_falling: Vec<(Point, i32)>,
-    points_added: Vec<Point>
+    points_added: Vec<Point>,
+    // the number of rows that were pushed in beneath the stack
+    rows_risen: usize,
 }
 
 impl BoardTransition {
@@ -32,11 +36,16 @@ impl BoardTransition {
         self.points_added.append(&mut points_added)
     }
 
+    pub fn add_rows_risen(&mut self, rows_risen: usize) {
+        self.rows_risen += rows_risen
+    }
+
     pub fn add_from_transition(&mut self, mut transition: BoardTransition) {
         self.points_deleted.append(&mut transition.points_deleted);
         self.rows_deleted.append(&mut transition.rows_deleted);
         self.points_falling.append(&mut transition.points_falling);
         self.points_added.append(&mut transition.points_added);
+        self.rows_risen += transition.rows_risen;
     }
 
     /**
@@ -108,6 +117,14 @@ impl BoardTransition {
         }
     }
 
+    pub fn get_rows_risen(&self) -> Option<usize> {
+        if self.rows_risen == 0 {
+            None
+        } else {
+            Some(self.rows_risen)
+        }
+    }
+
     pub fn take_points_deleted(&mut self) -> Option<Vec<Point>> {
         if self.points_deleted.is_empty() {
             None
@@ -136,6 +153,7 @@ impl BoardTransition {
         self.points_deleted.is_empty() && 
         self.points_falling.is_empty() && 
         self.points_added.is_empty() &&
-        self.rows_deleted.is_empty()
+        self.rows_deleted.is_empty() &&
+        self.rows_risen == 0
     }
 }
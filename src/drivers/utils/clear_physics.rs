@@ -0,0 +1,174 @@
+use crate::game_core::board::Board;
+use crate::game_core::cell_value::get_color;
+use crate::game_core::utils::point::Point;
+use super::recursive_physics::*;
+
+
+/**
+ * Decides how the rest of the board falls once rows or points have been cleared.
+ * The rows and points are given in their positions after the clear.
+ */
+pub trait ClearPhysics {
+    fn box_clone(&self) -> Box<dyn ClearPhysics>;
+
+    fn calculate_falls(&self, board: &Board, rows: &[i32], points: &[Point]) -> Vec<(Point, i32)>;
+
+    // once the board has settled, these physics should leave nothing floating
+    fn find_floating_points(&self, _board: &Board) -> Vec<(Point, i32)> {
+        Vec::new()
+    }
+}
+
+impl Clone for Box<dyn ClearPhysics> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+fn all_rows(board: &Board) -> Vec<i32> {
+    (0..board.num_active_rows() as i32).collect()
+}
+
+/**
+ * Collects the rows that were cleared along with the rows of any cleared points.
+ */
+fn affected_rows(rows: &[i32], points: &[Point]) -> Vec<i32> {
+    let mut rows = rows.iter().cloned().chain(points.iter().map(|p| p.y())).collect::<Vec<_>>();
+    rows.sort();
+    rows.dedup();
+    rows
+}
+
+/**
+ * Everything above a cleared row simply shifts down along with the rows, and cells above a
+ * cleared point drop straight down by the number of cleared points beneath them.
+ */
+#[derive(Clone)]
+pub struct NaivePhysics;
+
+impl NaivePhysics {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl ClearPhysics for NaivePhysics {
+    fn box_clone(&self) -> Box<dyn ClearPhysics> {
+        Box::new(self.clone())
+    }
+
+    fn calculate_falls(&self, board: &Board, _: &[i32], points: &[Point]) -> Vec<(Point, i32)> {
+        let mut falls = Vec::new();
+        if points.is_empty() {
+            return falls
+        }
+
+        for y in 0..board.num_active_rows() as i32 {
+            for x in 0..board.get_width() as i32 {
+                let point = Point(x, y);
+                let fall = points.iter().filter(|p| p.x() == x && p.y() < y).count() as i32;
+                if fall > 0 && board.is_point_filled(point) {
+                    falls.push((point, fall));
+                }
+            }
+        }
+
+        falls
+    }
+}
+
+/**
+ * Cells of the same color stick together, and fall as one shape.
+ */
+#[derive(Clone)]
+pub struct StickyPhysics;
+
+impl StickyPhysics {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl ClearPhysics for StickyPhysics {
+    fn box_clone(&self) -> Box<dyn ClearPhysics> {
+        Box::new(self.clone())
+    }
+
+    fn calculate_falls(&self, board: &Board, rows: &[i32], points: &[Point]) -> Vec<(Point, i32)> {
+        calculate_sticky_falls_from_rows_by_key(board, affected_rows(rows, points), get_color)
+    }
+
+    fn find_floating_points(&self, board: &Board) -> Vec<(Point, i32)> {
+        self.calculate_falls(board, &all_rows(board), &[])
+    }
+}
+
+/**
+ * The cells of each piece stick together, and fall as one shape.  This relies on every piece
+ * being given its own cell value, like the cascade generator does.
+ */
+#[derive(Clone)]
+pub struct CascadePhysics;
+
+impl CascadePhysics {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl ClearPhysics for CascadePhysics {
+    fn box_clone(&self) -> Box<dyn ClearPhysics> {
+        Box::new(self.clone())
+    }
+
+    fn calculate_falls(&self, board: &Board, rows: &[i32], points: &[Point]) -> Vec<(Point, i32)> {
+        calculate_sticky_falls_from_rows(board, affected_rows(rows, points))
+    }
+
+    fn find_floating_points(&self, board: &Board) -> Vec<(Point, i32)> {
+        self.calculate_falls(board, &all_rows(board), &[])
+    }
+}
+
+/**
+ * Every cell drops independently, until it lands on another cell or the floor.
+ */
+#[derive(Clone)]
+pub struct FullGravityPhysics;
+
+impl FullGravityPhysics {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl ClearPhysics for FullGravityPhysics {
+    fn box_clone(&self) -> Box<dyn ClearPhysics> {
+        Box::new(self.clone())
+    }
+
+    fn calculate_falls(&self, board: &Board, _: &[i32], _: &[Point]) -> Vec<(Point, i32)> {
+        calculate_full_gravity_falls(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naive_falls_into_an_emptied_bottom_row() {
+        let mut board = Board::new(4, 10);
+        board.fill_point(Point(0, 0), 1);
+        board.fill_point(Point(0, 1), 2);
+
+        let deleted = vec![Point(0, 0)];
+        board.clear_points(&deleted);
+        let falls = NaivePhysics::new().calculate_falls(&board, &[], &deleted);
+        board.translate_falling_points(&falls);
+
+        assert_eq!(falls, vec![(Point(0, 1), 1)]);
+        assert_eq!(board.get_cell(Point(0, 0)), Some(2));
+        assert_eq!(board.num_active_rows(), 1);
+    }
+}
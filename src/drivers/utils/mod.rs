@@ -1,3 +1,5 @@
 pub mod board_transition;
+pub mod clear_physics;
 pub mod recursive_physics;
+pub mod scoring;
 pub mod tetrimino_chooser;
\ No newline at end of file
@@ -4,7 +4,14 @@ use crate::game_core::board::Board;
 use crate::game_core::utils::point::Point;
 
 
-pub fn calculate_sticky_falls_from_rows(board: &Board, mut rows: Vec<i32>) -> Vec<(Point, i32)> {
+pub fn calculate_sticky_falls_from_rows(board: &Board, rows: Vec<i32>) -> Vec<(Point, i32)> {
+    calculate_sticky_falls_from_rows_by_key(board, rows, |value| value)
+}
+
+/**
+ * Same as `calculate_sticky_falls_from_rows`, except cells stick together whenever they share the same key.
+ */
+pub fn calculate_sticky_falls_from_rows_by_key(board: &Board, mut rows: Vec<i32>, key: fn(u32) -> u32) -> Vec<(Point, i32)> {
     rows.sort();
     let mut points = Vec::new();
     for (i, row) in rows.iter().enumerate() {
@@ -20,10 +27,18 @@ pub fn calculate_sticky_falls_from_rows(board: &Board, mut rows: Vec<i32>) -> Ve
         }
     }
     
-    calculate_sticky_falls(board, points)    
+    calculate_sticky_falls_by_key(board, points, key)    
+}
+
+pub fn calculate_sticky_falls(board: &Board, visit: Vec<Point>) -> Vec<(Point, i32)> {
+    calculate_sticky_falls_by_key(board, visit, |value| value)
 }
 
-pub fn calculate_sticky_falls(board: &Board, mut visit: Vec<Point>) -> Vec<(Point, i32)> {
+pub fn calculate_sticky_falls_by_key(board: &Board, mut visit: Vec<Point>, key: fn(u32) -> u32) -> Vec<(Point, i32)> {
+    if visit.is_empty() {
+        return Vec::new()
+    }
+
     let lowest_point = visit.iter().map(|p| p.y()).min().unwrap();
     let mut shapes = ShapeTracker::new(lowest_point, board);
 
@@ -38,7 +53,7 @@ pub fn calculate_sticky_falls(board: &Board, mut visit: Vec<Point>) -> Vec<(Poin
 
         // explore the shape
         let shape_index = shapes.new_shape();
-        let shape_value = board.get_cell(point).unwrap();
+        let shape_value = key(board.get_cell(point).unwrap());
         let mut shape_fall = board.get_height() as i32;
         let mut depended_shapes = Vec::new();
 
@@ -60,7 +75,7 @@ pub fn calculate_sticky_falls(board: &Board, mut visit: Vec<Point>) -> Vec<(Poin
                     // if the cell is off the point of simulated gravity, it will support this shape
                     if down.y() < lowest_point {
                         shape_fall = 0;
-                    } else if value == shape_value {
+                    } else if key(value) == shape_value {
                         shape_cells.push(down)
                     } else {
                         depended_shapes.push(ShapeDependency::adjacent(down));
@@ -86,7 +101,7 @@ pub fn calculate_sticky_falls(board: &Board, mut visit: Vec<Point>) -> Vec<(Poin
                 .filter(|p| board.is_on_board(*p) && (p.y() as usize) < board.num_active_rows());
             for adjacent in other_adjacents {
                 if let Some(value) = board.get_cell(adjacent) {
-                    if value == shape_value {
+                    if key(value) == shape_value {
                         shape_cells.push(adjacent)
                     } else {
                         visit.push(adjacent)
@@ -118,6 +133,75 @@ pub fn calculate_sticky_falls(board: &Board, mut visit: Vec<Point>) -> Vec<(Poin
 }
 
 
+/**
+ * Drops every cell on the board independently, until it lands on another cell or the floor.
+ */
+pub fn calculate_full_gravity_falls(board: &Board) -> Vec<(Point, i32)> {
+    let mut falls = Vec::new();
+    for x in 0..board.get_width() as i32 {
+        let mut empty_below = 0;
+        for y in 0..board.num_active_rows() as i32 {
+            let point = Point(x, y);
+            if board.is_point_filled(point) {
+                if empty_below > 0 {
+                    falls.push((point, empty_below));
+                }
+            } else {
+                empty_below += 1;
+            }
+        }
+    }
+
+    falls
+}
+
+/**
+ * Finds every group of at least `min_size` orthogonally connected cells sharing the same value.
+ */
+pub fn find_connected_groups(board: &Board, min_size: usize) -> Vec<Vec<Point>> {
+    let mut visited = vec![vec![false; board.get_width()]; board.num_active_rows()];
+    let mut groups = Vec::new();
+
+    for y in 0..board.num_active_rows() as i32 {
+        for x in 0..board.get_width() as i32 {
+            let start = Point(x, y);
+            let value = match board.get_cell(start) {
+                Some(value) if !visited[y as usize][x as usize] => value,
+                _ => continue
+            };
+
+            // flood fill all of the cells connected to the starting cell with the same value
+            visited[y as usize][x as usize] = true;
+            let mut group = vec![start];
+            let mut i = 0;
+            while i < group.len() {
+                let point = group[i];
+                i += 1;
+
+                let adjacents = [Point(0, 1), Point(0, -1), Point(1, 0), Point(-1, 0)]
+                    .iter()
+                    .map(|o| *o + point)
+                    .filter(|p| board.is_on_board(*p) && (p.y() as usize) < board.num_active_rows())
+                    .collect::<Vec<_>>();
+                for adjacent in adjacents {
+                    let is_visited = &mut visited[adjacent.y() as usize][adjacent.x() as usize];
+                    if !*is_visited && board.get_cell(adjacent) == Some(value) {
+                        *is_visited = true;
+                        group.push(adjacent);
+                    }
+                }
+            }
+
+            if group.len() >= min_size {
+                groups.push(group);
+            }
+        }
+    }
+
+    groups
+}
+
+
 #[derive(Clone, Copy, Debug)]
 enum ShapeDependency {
     Adjacent {
@@ -0,0 +1,77 @@
+use serde::{Serialize, Deserialize};
+
+use crate::drivers::DriverCore;
+
+
+/**
+ * The classic line clear scoring rules, shared by every mode that scores and
+ * levels up the same way the classic mode does.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClassicScoring {
+    last_clear_was_tetris: bool,
+}
+
+impl ClassicScoring {
+    pub fn new() -> Self {
+        Self {
+            last_clear_was_tetris: false
+        }
+    }
+
+    pub fn score_cleared_rows(&mut self, driver_core: &mut DriverCore, num_rows: usize) {
+        let score_update = match driver_core.count_lines(num_rows) {
+            1 => 1,
+            2 => 3,
+            3 => 5,
+            4 => if self.last_clear_was_tetris {
+                    12
+                } else {
+                    self.last_clear_was_tetris = true;
+                    8
+                }
+            _ => 0
+        };
+
+        self.last_clear_was_tetris = false;
+        Self::update_score(driver_core, score_update);
+    }
+
+    /**
+     * Rows cleared all at once at the end of a zone score more the more of them there are.
+     * Half the square is rounded up, so that even a single line scores.
+     */
+    pub fn score_zone_clear(&mut self, driver_core: &mut DriverCore, num_rows: usize) {
+        let lines = driver_core.count_lines(num_rows);
+        Self::update_score(driver_core, (lines * lines).div_ceil(2));
+    }
+
+    fn update_score(driver_core: &mut DriverCore, increment: usize) {
+        driver_core.score += increment;
+        let level = driver_core.score / 5;
+        if driver_core.level < level && level < 15 {
+            driver_core.level = level;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::*;
+    use crate::drivers::classic_driver::ClassicDriver;
+
+    #[test]
+    fn every_zone_clear_scores() {
+        let mut driver = DriverBuilder::<ClassicDriver>::new().build();
+        let mut scoring = ClassicScoring::new();
+
+        let mut last_score = 0;
+        for num_rows in 1..=20 {
+            scoring.score_zone_clear(driver.get_driver_core_mut(), num_rows);
+            let score = driver.get_score();
+            assert!(score > last_score);
+            last_score = score;
+        }
+    }
+}
@@ -1,13 +1,99 @@
 use rand::seq::SliceRandom;
-use rand::{SeedableRng, rngs::StdRng};
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Serialize, Deserialize};
 
 use crate::game_core::tetriminos::*;
 
 
+/**
+ * A seeded random number generator, which unlike the standard one can be saved along with the
+ * rest of a game's state.  It draws the same numbers as the standard generator seeded the same way.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "SeededRngState", into = "SeededRngState")]
+pub struct SeededRng {
+    seed: [u8; 32],
+    rng: ChaCha12Rng,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SeededRngState {
+    seed: [u8; 32],
+    // the number of 32 bit words drawn since the generator was seeded, which a game never gets near
+    // running out of 64 bits for
+    word_pos: u64,
+}
+
+impl From<SeededRng> for SeededRngState {
+    fn from(rng: SeededRng) -> Self {
+        Self {
+            seed: rng.seed,
+            word_pos: rng.rng.get_word_pos() as u64,
+        }
+    }
+}
+
+impl From<SeededRngState> for SeededRng {
+    fn from(state: SeededRngState) -> Self {
+        let mut rng = ChaCha12Rng::from_seed(state.seed);
+        rng.set_word_pos(state.word_pos as u128);
+        Self {
+            seed: state.seed,
+            rng,
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/**
+ * Seeds a random number generator with up to 32 bytes of the given seed.
+ */
+pub fn rng_from_seed(seed: &[u8]) -> SeededRng {
+    let mut seed_array: [u8; 32] = [0; 32];
+    for (i, b) in seed.iter().cloned().enumerate() {
+        if i >= 32 {
+            break;
+        }
+
+        seed_array[i] = b;
+    }
+
+    SeededRng {
+        seed: seed_array,
+        rng: ChaCha12Rng::from_seed(seed_array),
+    }
+}
+
+/**
+ * Creates a fresh random seed, which can be shared to give several games the same randomness.
+ */
+pub fn generate_rng_seed() -> Vec<u8> {
+    (0..32).map(|_| thread_rng().gen::<u8>()).collect()
+}
+
+#[derive(Clone)]
 pub struct TetriminoChooser {
     current_bucket: Vec<(usize, &'static TetriminoType)>,
     tetrimino_types: &'static [TetriminoType],
-    seeded_rng: StdRng,
+    seeded_rng: SeededRng,
 }
 
 impl TetriminoChooser {
@@ -15,21 +101,12 @@ impl TetriminoChooser {
         Self {
             current_bucket: Vec::new(),
             tetrimino_types,
-            seeded_rng: SeedableRng::from_seed([0; 32]),
+            seeded_rng: rng_from_seed(&[]),
         }
     }
 
     pub fn set_seed(&mut self, seed: Vec<u8>) {
-        let mut seed_array: [u8; 32] = [0; 32];
-        for (i, b) in seed.iter().cloned().enumerate() {
-            if i >= 32 {
-                break;
-            }
-
-            seed_array[i] = b;
-        }
-
-        self.seeded_rng = SeedableRng::from_seed(seed_array);
+        self.seeded_rng = rng_from_seed(&seed);
     }
 
     pub fn get_tetrimino_types(&self) -> &'static [TetriminoType] {
@@ -45,7 +122,53 @@ impl TetriminoChooser {
         self.current_bucket.pop().unwrap()
     }
 
-    pub fn get_seeded_rng(&mut self) -> &mut StdRng {
+    /**
+     * Starts a fresh bag, which the given tetrimino types have already been drawn from.
+     */
+    pub fn start_bag_without(&mut self, drawn: &[usize]) {
+        self.current_bucket = self.tetrimino_types
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !drawn.contains(index))
+            .collect();
+        self.current_bucket.shuffle(&mut self.seeded_rng);
+    }
+
+    /**
+     * The tetriminos left in the current bag, along with the random number generator.
+     */
+    pub fn save_state(&self) -> serde_json::Value {
+        let bag = self.current_bucket.iter().map(|&(index, _)| index).collect::<Vec<_>>();
+        serde_json::to_value((bag, &self.seeded_rng)).unwrap_or_default()
+    }
+
+    pub fn load_state(&mut self, state: &serde_json::Value) {
+        if let Ok((bag, seeded_rng)) = <(Vec<usize>, SeededRng)>::deserialize(state) {
+            let tetrimino_types = self.tetrimino_types;
+            self.current_bucket = bag
+                .into_iter()
+                .filter_map(|index| tetrimino_types.get(index).map(|tetrimino_type| (index, tetrimino_type)))
+                .collect();
+            self.seeded_rng = seeded_rng;
+        }
+    }
+
+    pub fn get_seeded_rng(&mut self) -> &mut SeededRng {
         &mut self.seeded_rng
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_rng_continues_where_it_left_off() {
+        let mut rng = rng_from_seed(&[1, 2, 3]);
+        rng.next_u64();
+
+        let mut restored: SeededRng = serde_json::from_value(serde_json::to_value(&rng).unwrap()).unwrap();
+        assert_eq!(restored.next_u64(), rng.next_u64());
+    }
+}
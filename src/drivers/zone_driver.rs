@@ -0,0 +1,194 @@
+use serde::{Serialize, Deserialize};
+
+use crate::drivers::*;
+use crate::game_core::defaults::settings::*;
+use super::utils::scoring::ClassicScoring;
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum ZoneState {
+    // line clears fill up the zone meter
+    Charging,
+    // gravity is frozen, and completed rows pile up at the bottom of the board until time runs out
+    Active { frames_remaining: usize },
+    // the retained rows are being cleared all at once
+    Ending,
+}
+
+/**
+ * Classic rules, with a meter that fills as lines are cleared.  Once it is full enough, the
+ * zone can be entered, which stops time and stacks up completed rows to be cleared together.
+ */
+pub struct ZoneDriver {
+    scoring: ClassicScoring,
+    driver_core: DriverCore,
+    zone_state: ZoneState,
+    // the number of lines cleared towards the next zone
+    zone_meter: usize,
+    best_zone: usize,
+}
+
+impl ZoneDriver {
+    fn is_zone_active(&self) -> bool {
+        matches!(self.zone_state, ZoneState::Active { .. })
+    }
+
+    /**
+     * While in the zone, any rows completed by the placed tetrimino are moved to the bottom of
+     * the board instead of being cleared.
+     */
+    fn retain_completed_rows(&mut self, mut transition: BoardTransition) -> BoardTransition {
+        if self.is_zone_active() {
+            if let Some(rows) = transition.take_rows_deleted() {
+                self.driver_core.core.get_board_mut().retain_rows(rows);
+                self.driver_core.core.active_tetrimino_updated();
+            }
+        }
+
+        transition
+    }
+
+    /**
+     * Clears every retained row at once.
+     */
+    fn end_zone(&mut self) -> BoardTransition {
+        self.zone_meter = 0;
+        let rows = self.driver_core.core.get_board_mut().release_retained_rows();
+        if rows.is_empty() {
+            self.zone_state = ZoneState::Charging;
+            BoardTransition::new()
+        } else {
+            self.zone_state = ZoneState::Ending;
+            BoardTransition::new().with_rows_deleted(rows)
+        }
+    }
+}
+
+impl BuildableDriver for ZoneDriver {
+    type Data = ();
+
+    fn build(mut builder: DriverBuilder<Self>) -> Self {
+        Self {
+            scoring: ClassicScoring::new(),
+            driver_core: builder.build_core(),
+            zone_state: ZoneState::Charging,
+            zone_meter: 0,
+            best_zone: 0,
+        }
+    }
+}
+
+impl Driver for ZoneDriver {
+    fn get_driver_core(&self) -> &DriverCore {
+        &self.driver_core
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        &mut self.driver_core
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        let zone = match self.zone_state {
+            ZoneState::Active { frames_remaining } => format!(
+                "zone: {} lines, {}s",
+                self.driver_core.core.get_board().num_retained_rows(),
+                frames_remaining / FRAMES_PER_SECOND),
+            _ if self.zone_meter >= ZONE_METER_MINIMUM => "zone: ready".to_string(),
+            _ => "zone: charging".to_string(),
+        };
+
+        vec![
+            zone,
+            format!("meter: {} / {}", self.zone_meter, ZONE_METER_CAPACITY),
+            format!("best zone: {}", self.best_zone),
+        ]
+    }
+
+    fn activate_zone(&mut self) {
+        if self.zone_state == ZoneState::Charging && self.zone_meter >= ZONE_METER_MINIMUM && !self.is_game_over() {
+            self.zone_state = ZoneState::Active {
+                frames_remaining: self.zone_meter * ZONE_FRAMES_PER_LINE
+            };
+        }
+    }
+
+    fn next_frame(&mut self) -> BoardTransition {
+        if let ZoneState::Active { frames_remaining } = self.zone_state {
+            if frames_remaining == 0 || self.is_game_over() {
+                return self.end_zone();
+            }
+
+            self.zone_state = ZoneState::Active { frames_remaining: frames_remaining - 1 };
+            // gravity is frozen, although the tetrimino can still be dropped by hand
+            if !self.driver_core.fastfalling {
+                self.driver_core.frames_since_drop = 0.0;
+            }
+        }
+
+        if self.driver_core.process_frame() {
+            self.fall()
+        } else {
+            BoardTransition::new()
+        }
+    }
+
+    fn fall(&mut self) -> BoardTransition {
+        let transition = self.driver_core.fall().1;
+        self.retain_completed_rows(transition)
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        let transition = self.driver_core.fastfall().1;
+        self.retain_completed_rows(transition)
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition { 
+        let (cleared_rows, _, new_transition) = self.driver_core.finish_transition(transition);
+
+        if let Some(rows) = cleared_rows {
+            if self.zone_state == ZoneState::Ending {
+                self.scoring.score_zone_clear(&mut self.driver_core, rows.len());
+                self.best_zone = std::cmp::max(self.best_zone, self.driver_core.count_lines(rows.len()));
+                self.zone_state = ZoneState::Charging;
+            } else {
+                self.scoring.score_cleared_rows(&mut self.driver_core, rows.len());
+                let lines = self.driver_core.count_lines(rows.len());
+                self.zone_meter = std::cmp::min(self.zone_meter + lines, ZONE_METER_CAPACITY);
+            }
+        }
+
+        new_transition
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        to_mode_state((&self.scoring, &self.zone_state, &self.zone_meter, &self.best_zone))
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        if let Some((scoring, zone_state, zone_meter, best_zone)) = from_mode_state(mode_state) {
+            self.scoring = scoring;
+            self.zone_state = zone_state;
+            self.zone_meter = zone_meter;
+            self.best_zone = best_zone;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restoring_a_snapshot_restores_the_zone() {
+        let mut driver = DriverBuilder::<ZoneDriver>::new().build();
+        driver.zone_meter = ZONE_METER_CAPACITY;
+        let snapshot = driver.take_snapshot();
+
+        driver.activate_zone();
+        assert!(driver.zone_state != ZoneState::Charging);
+
+        driver.restore_snapshot(&snapshot);
+        assert!(driver.zone_state == ZoneState::Charging);
+        assert_eq!(driver.zone_meter, ZONE_METER_CAPACITY);
+    }
+}
@@ -1,13 +1,32 @@
+use serde::{Serialize, Deserialize};
+
 use crate::game_core::tetriminos;
 use crate::game_core::utils::point::Point;
 
 
 type Cell = Option<u32>;
 
+/**
+ * Cells holding this value are garbage, rather than part of a placed tetrimino.
+ */
+pub const GARBAGE_VALUE: u32 = u32::MAX;
+
+// at most this many violations are kept until they are taken
+const MAX_VIOLATIONS: usize = 100;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
-    cells: Vec<(Vec<Cell>, usize)>,
+    // each row holds its cells, the number of filled cells, and the frame each cell was filled on
+    cells: Vec<(Vec<Cell>, usize, Vec<usize>)>,
     height: usize,
     width: usize,
+    // the frame that newly filled cells are stamped with
+    current_frame: usize,
+    // the number of completed rows held at the bottom of the board, instead of being cleared
+    retained_rows: usize,
+    // problems found while the cells were changed, only looked for in debug builds
+    #[serde(skip)]
+    violations: Vec<String>,
 }
 
 impl Board {
@@ -16,9 +35,42 @@ impl Board {
             cells: Vec::new(),
             width,
             height,
+            current_frame: 0,
+            retained_rows: 0,
+            violations: Vec::new(),
         }
     }
 
+    fn report_violation(&mut self, violation: String) {
+        if cfg!(feature = "debug") && self.violations.len() < MAX_VIOLATIONS {
+            self.violations.push(violation);
+        }
+    }
+
+    /**
+     * Checks that a row's count still agrees with its cells right after it was changed, so that
+     * any drift is caught where it happens.
+     */
+    fn check_row(&mut self, y: usize) {
+        if !cfg!(feature = "debug") || y >= self.cells.len() {
+            return
+        }
+
+        let (row, count, _) = &self.cells[y];
+        let filled = row.iter().filter(|cell| cell.is_some()).count();
+        if filled != *count {
+            let violation = format!("row {} has {} filled cells, but counts {}", y, filled, count);
+            self.report_violation(violation);
+        }
+    }
+
+    /**
+     * Takes the problems found while the cells were changed, since they were last taken.
+     */
+    pub fn take_violations(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.violations)
+    }
+
     fn get_row_mut(&mut self, index: usize) -> &mut Vec<Cell> {
         &mut self.cells[index].0
     }
@@ -35,39 +87,134 @@ impl Board {
         self.get_row(point.y() as usize)[point.x() as usize]
     }
 
+    /**
+     * Returns the cell at the given point, or nothing if its row isn't active.
+     */
+    fn get_cell_if_active(&self, point: Point) -> Cell {
+        if point.y() >= 0 && (point.y() as usize) < self.cells.len() {
+            self.get_cell(point)
+        } else {
+            None
+        }
+    }
+
     pub fn get_cell_mut(&mut self, point: Point) -> &mut Cell {
         &mut self.get_row_mut(point.y() as usize)[point.x() as usize]
     }
 
+    /**
+     * Returns the frame on which the cell at the given point was filled.
+     */
+    pub fn get_fill_frame(&self, point: Point) -> usize {
+        self.cells[point.y() as usize].2[point.x() as usize]
+    }
+
+    pub fn get_current_frame(&self) -> usize {
+        self.current_frame
+    }
+
+    pub fn set_current_frame(&mut self, current_frame: usize) {
+        self.current_frame = current_frame;
+    }
+
+    fn empty_row(&self) -> (Vec<Cell>, usize, Vec<usize>) {
+        (vec![None; self.width], 0, vec![self.current_frame; self.width])
+    }
+
     pub fn fill_point(&mut self, point: Point, value: u32) -> bool {
+        self.fill_point_on_frame(point, value, self.current_frame)
+    }
+
+    fn fill_point_on_frame(&mut self, point: Point, value: u32, frame: usize) -> bool {
         while (point.y() as usize) >= self.cells.len() {
             if self.cells.len() + 1 >= self.height {
                 return false;
             }
 
-            self.cells.push((vec![None; self.width], 0));
+            self.cells.push(self.empty_row());
         }
 
         if !self.get_cell(point).is_some() {
             *self.get_row_count_mut(point.y()) += 1;
             self.get_row_mut(point.y() as usize)[point.x() as usize] = Some(value);
+            self.cells[point.y() as usize].2[point.x() as usize] = frame;
+            self.check_row(point.y() as usize);
         }
 
         return true;
     }
 
     pub fn unfill_point(&mut self, point: Point) {
-        let row_count = self.get_row_count_mut(point.y());
-        *row_count -= 1;
-        if *row_count == 0 && point.y() == self.num_active_rows() as i32 - 1 {
-            let mut i = point.y();
-            while *self.get_row_count_mut(i) == 0 {
-                self.cells.remove(i as usize);
-                i -= 1;
-            }
+        if (point.y() as usize) >= self.cells.len() || self.get_cell(point).is_none() {
+            return
+        }
+
+        *self.get_cell_mut(point) = None;
+        let y = point.y() as usize;
+        if self.cells[y].1 == 0 {
+            // the count had already drifted, so it is recounted rather than underflowing
+            self.report_violation(format!("row {} counted no filled cells, but {:?} was filled", y, point));
+            self.cells[y].1 = self.cells[y].0.iter().filter(|cell| cell.is_some()).count();
         } else {
-            *self.get_cell_mut(point) = None;
+            self.cells[y].1 -= 1;
+            self.check_row(y);
+        }
+
+        // empty rows at the top of the stack are no longer active
+        while let Some((row, 0, _)) = self.cells.last() {
+            if row.iter().any(|cell| cell.is_some()) {
+                let violation = format!("the top active row {} counts no filled cells, but is not empty", self.cells.len() - 1);
+                self.report_violation(violation);
+                break;
+            }
+
+            self.cells.pop();
+        }
+    }
+
+    /**
+     * Fills an entire row with garbage, except for the given holes.
+     */
+    pub fn fill_garbage_row(&mut self, row: i32, holes: &[i32]) -> bool {
+        for x in 0..self.width as i32 {
+            if !holes.contains(&x) && !self.fill_point(Point(x, row), GARBAGE_VALUE) {
+                return false;
+            }
         }
+
+        true
+    }
+
+    /**
+     * Pushes the whole stack up, inserting a garbage row with the given holes at
+     * the bottom of the board.  Returns false if the stack overflows.
+     */
+    pub fn insert_garbage_row(&mut self, holes: &[i32]) -> bool {
+        if self.cells.len() + 1 >= self.height {
+            return false;
+        }
+
+        let row = (0..self.width as i32)
+            .map(|x| if holes.contains(&x) { None } else { Some(GARBAGE_VALUE) })
+            .collect::<Vec<_>>();
+        let count = row.iter().filter(|cell| cell.is_some()).count();
+        self.cells.insert(0, (row, count, vec![self.current_frame; self.width]));
+
+        true
+    }
+
+    pub fn is_garbage(&self, point: Point) -> bool {
+        (point.y() as usize) < self.cells.len() && self.get_cell(point) == Some(GARBAGE_VALUE)
+    }
+
+    pub fn is_garbage_row(&self, row: i32) -> bool {
+        (0..self.width as i32).any(|x| self.is_garbage(Point(x, row)))
+    }
+
+    pub fn num_garbage_rows(&self) -> usize {
+        (0..self.cells.len() as i32)
+            .filter(|&row| self.is_garbage_row(row))
+            .count()
     }
 
     pub fn is_point_filled(&self, point: Point) -> bool {
@@ -100,7 +247,7 @@ impl Board {
         let mut rows = Vec::new();
 
         for (i, point) in tetrimino.get_points().into_iter().enumerate() {
-            if !self.fill_point(point, tetrimino.get_tetrimino().values[i]) {
+            if !self.fill_point(point, tetrimino.get_point_value(i)) {
                 return None;
             }
 
@@ -123,23 +270,90 @@ impl Board {
         }
     }
 
+    /**
+     * Replaces the value of every filled cell on the board.
+     */
+    pub fn map_cells(&mut self, mut f: impl FnMut(u32) -> u32) {
+        for value in self.cells.iter_mut().flat_map(|row| row.0.iter_mut().flatten()) {
+            *value = f(*value);
+        }
+    }
+
+    /**
+     * Copies out the value of every cell in the active rows, from the bottom row up.
+     */
+    pub fn get_cells(&self) -> Vec<Vec<Cell>> {
+        self.cells.iter().map(|row| row.0.clone()).collect()
+    }
+
+    /**
+     * Replaces the whole board with the given cells, as copied out by `get_cells`.
+     */
+    pub fn set_cells(&mut self, rows: &[Vec<Cell>]) {
+        self.clear();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Some(value) = cell {
+                    self.fill_point(Point(x as i32, y as i32), *value);
+                }
+            }
+        }
+    }
+
+    /**
+     * Removes every cell from the board.
+     */
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.retained_rows = 0;
+    }
+
+    /**
+     * Moves the given completed rows down to the bottom of the board, on top of any rows
+     * that are already retained there, instead of clearing them.
+     */
+    pub fn retain_rows(&mut self, mut rows: Vec<i32>) {
+        rows.sort();
+        for row in rows.into_iter() {
+            // moving the rows beneath this one doesn't change its index
+            let completed_row = self.cells.remove(row as usize);
+            self.cells.insert(self.retained_rows, completed_row);
+            self.retained_rows += 1;
+        }
+    }
+
+    pub fn num_retained_rows(&self) -> usize {
+        self.retained_rows
+    }
+
+    /**
+     * Stops retaining the completed rows at the bottom of the board, and returns them so they can be cleared.
+     */
+    pub fn release_retained_rows(&mut self) -> Vec<i32> {
+        let rows = (0..self.retained_rows as i32).collect();
+        self.retained_rows = 0;
+        rows
+    }
+
     pub fn clear_points(&mut self, points: &Vec<Point>) {
         for &point in points.iter() {
             self.unfill_point(point);
         }
     }
 
-    pub fn translate_falling_points(&mut self, point_drops: &Vec<(Point, i32)>) -> Vec<i32> {
+    pub fn translate_falling_points(&mut self, point_drops: &[(Point, i32)]) -> Vec<i32> {
         let mut rows = Vec::new();
-        for (point, fall) in point_drops.into_iter().cloned() {
-            let cell = self.get_cell_mut(point);
-            if let Some(value) = *cell {
+        for (point, fall) in point_drops.iter().cloned() {
+            let destination = point - Point::unit_y(fall);
+            if let Some(value) = self.get_cell_if_active(point) {
+                // falling cells keep the frame they were originally filled on
+                let frame = self.get_fill_frame(point);
                 self.unfill_point(point);
-                self.fill_point(point - Point::unit_y(fall), value);
+                self.fill_point_on_frame(destination, value, frame);
             }
 
-            if *self.get_row_count_mut(point.y() - fall) == self.width {
-                rows.push(point.y() - fall)
+            if (destination.y() as usize) < self.cells.len() && *self.get_row_count_mut(destination.y()) == self.width {
+                rows.push(destination.y())
             }
         }
 
@@ -167,6 +381,42 @@ impl Board {
             .unwrap()
     }
 
+    /**
+     * Checks that the bookkeeping kept alongside the cells still agrees with them, returning
+     * a description of every disagreement found.
+     */
+    pub fn find_inconsistencies(&self) -> Vec<String> {
+        let mut inconsistencies = Vec::new();
+        if self.cells.len() >= self.height {
+            inconsistencies.push(format!("{} active rows on a board {} rows tall", self.cells.len(), self.height));
+        }
+
+        for (y, (row, count, fill_frames)) in self.cells.iter().enumerate() {
+            let filled = row.iter().filter(|cell| cell.is_some()).count();
+            if filled != *count {
+                inconsistencies.push(format!("row {} has {} filled cells, but counts {}", y, filled, count));
+            }
+
+            if row.len() != self.width || fill_frames.len() != self.width {
+                inconsistencies.push(format!("row {} is {} cells wide with {} fill frames", y, row.len(), fill_frames.len()));
+            }
+        }
+
+        if self.cells.last().is_some_and(|(row, _, _)| row.iter().all(|cell| cell.is_none())) {
+            inconsistencies.push(format!("the top active row {} is empty", self.cells.len() - 1));
+        }
+
+        if self.retained_rows > self.cells.len() {
+            inconsistencies.push(format!("{} retained rows, but only {} active rows", self.retained_rows, self.cells.len()));
+        }
+
+        inconsistencies
+    }
+
+    pub fn num_filled_cells(&self) -> usize {
+        self.cells.iter().map(|(row, _, _)| row.iter().filter(|cell| cell.is_some()).count()).sum()
+    }
+
     pub fn num_active_rows(&self) -> usize {
         self.cells.len()
     }
@@ -193,3 +443,55 @@ impl Board {
         Point::new(self.width as i32 / 2, self.height as i32 - 1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_row(board: &mut Board, y: i32) {
+        for x in 0..board.get_width() as i32 {
+            board.fill_point(Point(x, y), 1);
+        }
+    }
+
+    #[test]
+    fn clearing_every_cell_empties_the_board() {
+        let mut board = Board::new(4, 10);
+        fill_row(&mut board, 0);
+
+        board.clear_points(&(0..4).map(|x| Point(x, 0)).collect());
+
+        assert_eq!(board.num_active_rows(), 0);
+        assert!(board.find_inconsistencies().is_empty());
+    }
+
+    #[test]
+    fn points_fall_into_a_row_that_was_just_emptied() {
+        let mut board = Board::new(4, 10);
+        fill_row(&mut board, 0);
+        board.fill_point(Point(0, 1), 2);
+
+        board.clear_points(&(0..4).map(|x| Point(x, 0)).collect());
+        let full_rows = board.translate_falling_points(&[(Point(0, 1), 1)]);
+
+        assert!(full_rows.is_empty());
+        assert_eq!(board.num_active_rows(), 1);
+        assert_eq!(board.get_cell(Point(0, 0)), Some(2));
+        assert_eq!(board.num_filled_cells(), 1);
+        assert!(board.find_inconsistencies().is_empty());
+    }
+
+    #[test]
+    fn a_drifted_row_count_is_recounted() {
+        let mut board = Board::new(4, 10);
+        board.fill_point(Point(0, 0), 1);
+        board.fill_point(Point(1, 0), 1);
+        board.cells[0].1 = 0;
+
+        board.unfill_point(Point(0, 0));
+
+        assert_eq!(board.num_active_rows(), 1);
+        assert!(board.find_inconsistencies().is_empty());
+        assert_eq!(board.take_violations().len(), if cfg!(feature = "debug") { 1 } else { 0 });
+    }
+}
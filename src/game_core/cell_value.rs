@@ -0,0 +1,65 @@
+/*!
+ * Cell values are split into parts.  The lowest bits hold the color, the upper bits tag
+ * the cell with an item, and the bits in between identify which piece the cell belongs to.
+ */
+
+pub const COLOR_MASK: u32 = 0xF;
+pub const IDENTITY_SHIFT: u32 = 4;
+pub const ITEM_SHIFT: u32 = 28;
+pub const ITEM_MASK: u32 = 0xF << ITEM_SHIFT;
+pub const IDENTITY_MASK: u32 = !(ITEM_MASK | COLOR_MASK);
+pub const MAX_IDENTITY: u32 = IDENTITY_MASK >> IDENTITY_SHIFT;
+
+pub fn get_color(value: u32) -> u32 {
+    value & COLOR_MASK
+}
+
+pub fn get_identity(value: u32) -> u32 {
+    (value & IDENTITY_MASK) >> IDENTITY_SHIFT
+}
+
+pub fn with_identity(value: u32, identity: u32) -> u32 {
+    (value & !IDENTITY_MASK) | ((identity << IDENTITY_SHIFT) & IDENTITY_MASK)
+}
+
+pub fn get_item(value: u32) -> u32 {
+    (value & ITEM_MASK) >> ITEM_SHIFT
+}
+
+pub fn with_item(value: u32, item: u32) -> u32 {
+    without_item(value) | ((item << ITEM_SHIFT) & ITEM_MASK)
+}
+
+pub fn without_item(value: u32) -> u32 {
+    value & !ITEM_MASK
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Item {
+    // clears the 3x3 area around it
+    Bomb = 1,
+    // clears its entire column
+    Laser = 2,
+    // collapses the whole stack, dropping every cell independently
+    Gravity = 3,
+    // clears every cell with the same color
+    ColorBomb = 4,
+}
+
+impl Item {
+    pub const ALL: [Item; 4] = [Item::Bomb, Item::Laser, Item::Gravity, Item::ColorBomb];
+
+    pub fn from_value(value: u32) -> Option<Self> {
+        let item = get_item(value);
+        Self::ALL.iter().cloned().find(|i| *i as u32 == item)
+    }
+
+    pub fn get_glyph(self) -> &'static str {
+        match self {
+            Item::Bomb => "B",
+            Item::Laser => "L",
+            Item::Gravity => "G",
+            Item::ColorBomb => "C",
+        }
+    }
+}
\ No newline at end of file
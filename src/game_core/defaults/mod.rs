@@ -2,11 +2,22 @@ pub mod tetriminos;
 
 pub mod settings {
     pub const QUEUE_LENGTH: usize = 3;
+    pub const FRAMES_PER_SECOND: usize = 60;
+    pub const ULTRA_TIME_LIMIT: usize = 3 * 60 * FRAMES_PER_SECOND;
+    pub const SHORT_ULTRA_TIME_LIMIT: usize = 2 * 60 * FRAMES_PER_SECOND;
+    pub const DIG_GARBAGE_ROWS: usize = 10;
+    pub const FADING_STACK_FRAMES: usize = 5 * FRAMES_PER_SECOND;
+    pub const LINE_CLEAR_FLASH_FRAMES: usize = FRAMES_PER_SECOND / 2;
+    pub const ZONE_METER_CAPACITY: usize = 16;
+    pub const ZONE_METER_MINIMUM: usize = 4;
+    pub const ZONE_FRAMES_PER_LINE: usize = 5 * FRAMES_PER_SECOND / 4;
 }
 
 pub mod dimensions {
     pub const CELL_WIDTH: usize = 10;
     pub const CELL_HEIGHT: usize = 20;
+    // the shared board in co-op is twice as wide, to fit both players
+    pub const COOP_CELL_WIDTH: usize = 20;
 }
 
 pub mod gravity {
@@ -96,6 +96,9 @@ pub const TETRIMINOS: &[TetriminoType] = &[
     J_TETRIMINO,
 ];
 
+// the letter naming each tetrimino, in the same order, for writing out piece sequences and fields
+pub const TETRIMINO_NAMES: &str = "ITOSZLJ";
+
 pub const I_WALL_KICKS: [[&[Point]; 2]; 4] = [
     // Origin
     // Clockwise, Counterclockwise
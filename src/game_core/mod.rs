@@ -1,15 +1,31 @@
 #[macro_use]
 pub mod tetriminos;
 pub mod board;
+pub mod cell_value;
 pub mod defaults;
 pub mod utils;
 
+use serde::{Serialize, Deserialize};
+
 use tetriminos::*;
 use utils::point::Point;
 use utils::orientations::*;
 
 
-pub struct GameCore {
+/**
+ * Describes how the last tetrimino was placed on the board.
+ */
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Placement {
+    // a t tetrimino was rotated into a spot with at least three of its corners filled
+    pub t_spin: bool,
+}
+
+/**
+ * A tetrimino under a player's control, along with their own hold and queue.
+ */
+#[derive(Clone)]
+pub struct Piece {
     active_tetrimino: ActiveTetrimino,
     ghost_tetrimino: Vec<Point>,
     held_tetrimino: Option<Tetrimino>,
@@ -17,8 +33,117 @@ pub struct GameCore {
     tetrimino_queue: Vec<Tetrimino>,
     next_tetrimino_index: usize,
 
+    // whether the active tetrimino was rotated since it last moved, to detect spins
+    last_move_rotated: bool,
+    last_placement: Placement,
+
+    // where this piece spawns, relative to the board's spawn point
+    spawn_offset: Point,
+    // the active tetrimino is waiting for another piece to move off of its spawn point, so
+    // it is not yet on the board
+    waiting_to_spawn: bool,
+}
+
+impl Piece {
+    fn new(active_tetrimino: ActiveTetrimino, tetrimino_queue: Vec<Tetrimino>, spawn_offset: Point) -> Self {
+        Self {
+            ghost_tetrimino: active_tetrimino.get_points(),
+            active_tetrimino,
+            held_tetrimino: None,
+            tetrimino_queue,
+            next_tetrimino_index: 0,
+            last_move_rotated: false,
+            last_placement: Placement::default(),
+            spawn_offset,
+            waiting_to_spawn: false,
+        }
+    }
+
+    pub fn get_active_tetrimino(&self) -> &ActiveTetrimino {
+        &self.active_tetrimino
+    }
+
+    pub fn get_ghost_tetrimino(&self) -> &Vec<Point> {
+        &self.ghost_tetrimino
+    }
+
+    pub fn get_held(&self) -> Option<&Tetrimino> {
+        self.held_tetrimino.as_ref()
+    }
+
+    pub fn get_next_tetrimino(&self, index: usize) -> &Tetrimino {
+        &self.tetrimino_queue[(self.next_tetrimino_index + index) % self.tetrimino_queue.len()]
+    }
+
+    /**
+     * Replaces the value of every cell in the active, held and queued tetriminos.  The queue is
+     * visited in the order its tetriminos will be played.
+     */
+    fn map_cell_values(&mut self, f: &mut impl FnMut(u32) -> u32) {
+        let held = self.held_tetrimino.iter_mut();
+        for tetrimino in held.chain(std::iter::once(&mut self.active_tetrimino.tetrimino)) {
+            tetrimino.values.iter_mut().for_each(|value| *value = f(*value));
+        }
+
+        let queue_length = self.tetrimino_queue.len();
+        for i in 0..queue_length {
+            let index = (self.next_tetrimino_index + i) % queue_length;
+            self.tetrimino_queue[index].values.iter_mut().for_each(|value| *value = f(*value));
+        }
+    }
+}
+
+/**
+ * A tetrimino, saved as the index of its type among the generator's tetrimino types.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedTetrimino {
+    type_index: usize,
+    values: Vec<u32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedPiece {
+    active_tetrimino: SavedTetrimino,
+    position: Point,
+    orientation: Orientation,
+    held_tetrimino: Option<SavedTetrimino>,
+    tetrimino_queue: Vec<SavedTetrimino>,
+    next_tetrimino_index: usize,
+    last_move_rotated: bool,
+    last_placement: Placement,
+    spawn_offset: Point,
+    #[serde(default)]
+    waiting_to_spawn: bool,
+}
+
+/**
+ * Everything about a game core that changes while it is played.  Loading it into a core built
+ * with the same settings picks the game up from the same place.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameCoreState {
+    // every piece, in order
+    pieces: Vec<SavedPiece>,
+    selected_piece: usize,
+    board: board::Board,
+    tetrimino_generator: serde_json::Value,
+}
+
+#[derive(Clone)]
+pub struct GameCore {
+    // the selected piece, which every movement applies to
+    piece: Piece,
+    // every other piece, indexed by piece, with an empty slot for the selected piece
+    parked_pieces: Vec<Option<Piece>>,
+    selected_piece: usize,
+
     board: board::Board,
 
+    // each block of a tetrimino covers piece_scale x piece_scale cells, and moves movement_step cells at a time
+    piece_scale: i32,
+    movement_step: i32,
+
     tetrimino_generator: Box<dyn TetriminoGenerator>
 }
 
@@ -36,17 +161,247 @@ impl GameCore {
             .map(|_| tetrimino_generator.next())
             .collect::<Vec<_>>();
 
+        let piece = Piece::new(active_tetrimino, tetrimino_queue, Point(0, 0));
+
         Self {
-            ghost_tetrimino: active_tetrimino.get_points(),
-            active_tetrimino,
-            held_tetrimino: None,
-            tetrimino_queue,
-            next_tetrimino_index: 0,
+            piece,
+            parked_pieces: vec![None],
+            selected_piece: 0,
             board,
+            piece_scale: 1,
+            movement_step: 1,
             tetrimino_generator,
         }
     }
 
+    /**
+     * Scales up every tetrimino, so that each block covers piece_scale x piece_scale cells.
+     */
+    pub fn with_piece_scale(mut self, piece_scale: i32, movement_step: i32) -> Self {
+        self.piece_scale = piece_scale;
+        self.movement_step = movement_step;
+        let tetrimino = self.piece.active_tetrimino.tetrimino.clone();
+        self.piece.active_tetrimino = self.spawn(tetrimino);
+        self.active_tetrimino_updated();
+        self
+    }
+
+    pub fn save_state(&self) -> GameCoreState {
+        let tetrimino_types = self.tetrimino_generator.get_tetrimino_types();
+        let save_tetrimino = |tetrimino: &Tetrimino| SavedTetrimino {
+            type_index: tetrimino_types
+                .iter()
+                .position(|tetrimino_type| std::ptr::eq(tetrimino_type, tetrimino.tetrimino_type))
+                .unwrap_or(0),
+            values: tetrimino.values.clone(),
+        };
+
+        let pieces = self.get_pieces()
+            .map(|piece| SavedPiece {
+                active_tetrimino: save_tetrimino(&piece.active_tetrimino.tetrimino),
+                position: piece.active_tetrimino.position,
+                orientation: piece.active_tetrimino.orientation,
+                held_tetrimino: piece.held_tetrimino.as_ref().map(save_tetrimino),
+                tetrimino_queue: piece.tetrimino_queue.iter().map(save_tetrimino).collect(),
+                next_tetrimino_index: piece.next_tetrimino_index,
+                last_move_rotated: piece.last_move_rotated,
+                last_placement: piece.last_placement,
+                spawn_offset: piece.spawn_offset,
+                waiting_to_spawn: piece.waiting_to_spawn,
+            })
+            .collect();
+
+        GameCoreState {
+            pieces,
+            selected_piece: self.selected_piece,
+            board: self.board.clone(),
+            tetrimino_generator: self.tetrimino_generator.save_state(),
+        }
+    }
+
+    pub fn load_state(&mut self, state: &GameCoreState) {
+        let tetrimino_types = self.tetrimino_generator.get_tetrimino_types();
+        let load_tetrimino = |saved: &SavedTetrimino| tetrimino_types[saved.type_index].instance(saved.values.clone());
+
+        let mut pieces = state.pieces
+            .iter()
+            .map(|saved| {
+                let mut active_tetrimino = load_tetrimino(&saved.active_tetrimino)
+                    .as_active_instance(saved.position)
+                    .scaled(self.piece_scale);
+                active_tetrimino.orientation = saved.orientation;
+
+                let mut piece = Piece::new(active_tetrimino, saved.tetrimino_queue.iter().map(load_tetrimino).collect(), saved.spawn_offset);
+                piece.held_tetrimino = saved.held_tetrimino.as_ref().map(load_tetrimino);
+                piece.next_tetrimino_index = saved.next_tetrimino_index;
+                piece.last_move_rotated = saved.last_move_rotated;
+                piece.last_placement = saved.last_placement;
+                piece.waiting_to_spawn = saved.waiting_to_spawn;
+                Some(piece)
+            })
+            .collect::<Vec<_>>();
+
+        self.piece = pieces[state.selected_piece].take().unwrap();
+        self.parked_pieces = pieces;
+        self.selected_piece = state.selected_piece;
+        self.board = state.board.clone();
+        self.tetrimino_generator.load_state(&state.tetrimino_generator);
+        self.active_tetrimino_updated();
+    }
+
+    pub fn get_piece_scale(&self) -> i32 {
+        self.piece_scale
+    }
+
+    pub fn get_movement_step(&self) -> i32 {
+        self.movement_step
+    }
+
+    /**
+     * Places a tetrimino at the spawn point, shifted down so that scaled tetriminos still fit
+     * beneath the top of the board.
+     */
+    fn spawn(&self, tetrimino: Tetrimino) -> ActiveTetrimino {
+        self.spawn_with_offset(tetrimino, self.piece.spawn_offset)
+    }
+
+    fn spawn_with_offset(&self, tetrimino: Tetrimino, spawn_offset: Point) -> ActiveTetrimino {
+        let scale_offset = Point(0, self.piece_scale - 1);
+        let spawn_point = self.board.get_spawn_point() + spawn_offset;
+        tetrimino.as_active_instance(spawn_point - scale_offset).scaled(self.piece_scale)
+    }
+
+    /**
+     * Adds another piece, for another player to control at the same time.  It is given its
+     * own hold and queue, and spawns offset from the board's spawn point.
+     */
+    pub fn add_piece(&mut self, spawn_offset: Point) {
+        let active_tetrimino = self.tetrimino_generator.next();
+        let active_tetrimino = self.spawn_with_offset(active_tetrimino, spawn_offset);
+        let tetrimino_queue = (0..self.piece.tetrimino_queue.len())
+            .map(|_| self.tetrimino_generator.next())
+            .collect::<Vec<_>>();
+
+        self.parked_pieces.push(Some(Piece::new(active_tetrimino, tetrimino_queue, spawn_offset)));
+    }
+
+    /**
+     * Moves the spawn point of the selected piece, and respawns its active tetrimino there.
+     */
+    pub fn set_spawn_offset(&mut self, spawn_offset: Point) {
+        self.piece.spawn_offset = spawn_offset;
+        let tetrimino = self.piece.active_tetrimino.tetrimino.clone();
+        self.set_active_tetrimino(self.spawn(tetrimino));
+    }
+
+    pub fn get_num_pieces(&self) -> usize {
+        self.parked_pieces.len()
+    }
+
+    pub fn get_selected_piece(&self) -> usize {
+        self.selected_piece
+    }
+
+    /**
+     * Selects the piece that every following movement applies to.
+     */
+    pub fn select_piece(&mut self, index: usize) {
+        if index == self.selected_piece {
+            return
+        }
+
+        let piece = self.parked_pieces[index].take().unwrap();
+        let parked = std::mem::replace(&mut self.piece, piece);
+        self.parked_pieces[self.selected_piece] = Some(parked);
+        self.selected_piece = index;
+
+        // the other pieces may have moved since this one's ghost was last placed
+        self.active_tetrimino_updated();
+    }
+
+    pub fn get_piece(&self, index: usize) -> &Piece {
+        if index == self.selected_piece {
+            &self.piece
+        } else {
+            self.parked_pieces[index].as_ref().unwrap()
+        }
+    }
+
+    pub fn get_pieces(&self) -> impl Iterator<Item = &Piece> {
+        (0..self.get_num_pieces()).map(move |i| self.get_piece(i))
+    }
+
+    /**
+     * Finds the points covered by every other piece on the board.  A piece waiting to spawn
+     * covers nothing.
+     */
+    fn get_parked_points(&self) -> Vec<Point> {
+        self.parked_pieces
+            .iter()
+            .flatten()
+            .filter(|piece| !piece.waiting_to_spawn)
+            .flat_map(|piece| piece.active_tetrimino.get_points())
+            .collect()
+    }
+
+    /**
+     * Checks that the points are free on the board, and are not covered by any other piece.
+     */
+    fn do_points_fit(&self, points: Vec<Point>) -> bool {
+        let parked_points = self.get_parked_points();
+        !points.iter().any(|p| parked_points.contains(p)) && self.board.do_points_fit(points)
+    }
+
+    /**
+     * Checks whether another piece covers any point of the selected piece's active tetrimino,
+     * which happens when a tetrimino spawns where another player's piece is.
+     */
+    pub fn is_spawn_blocked(&self) -> bool {
+        let parked_points = self.get_parked_points();
+        self.piece.active_tetrimino.get_points().iter().any(|p| parked_points.contains(p))
+    }
+
+    /**
+     * Keeps the selected piece off of the board until it is set to spawn, so that it does not
+     * block the piece it is waiting on.
+     */
+    pub fn set_waiting_to_spawn(&mut self, waiting_to_spawn: bool) {
+        self.piece.waiting_to_spawn = waiting_to_spawn;
+    }
+
+    pub fn is_waiting_to_spawn(&self) -> bool {
+        self.piece.waiting_to_spawn
+    }
+
+    pub fn does_active_tetrimino_fit(&self) -> bool {
+        self.do_points_fit(self.piece.active_tetrimino.get_points())
+    }
+
+    /**
+     * Finds how far the points can drop before landing on the board or on any other piece.
+     * Tetriminos only drop whole movement steps, so that they stay on the grid they move on.
+     */
+    fn first_collision(&self, points: Vec<Point>) -> Point {
+        let step = self.movement_step;
+        let board_collision = self.board.first_collision(points.clone());
+        let board_collision = Point(0, board_collision.y() / step * step);
+        if self.parked_pieces.iter().all(|piece| piece.is_none()) {
+            return board_collision
+        }
+
+        let mut translation = Point(0, 0);
+        while translation.y() > board_collision.y() {
+            let next_translation = translation - Point::unit_y(step);
+            if !self.do_points_fit(points.iter().map(|p| *p + next_translation).collect()) {
+                break;
+            }
+
+            translation = next_translation;
+        }
+
+        translation
+    }
+
     pub fn get_board(&self) -> &board::Board {
         &self.board
     }
@@ -56,19 +411,15 @@ impl GameCore {
     }
 
     pub fn get_active_tetrimino(&self) -> &ActiveTetrimino {
-        &self.active_tetrimino
-    }
-
-    pub fn get_ghost_tetriminio(&self) -> &Vec<Point> {
-        &self.ghost_tetrimino
+        self.piece.get_active_tetrimino()
     }
 
     pub fn get_next_tetrimino(&self, index: usize) -> &Tetrimino {
-        &self.tetrimino_queue[(self.next_tetrimino_index + index) % self.tetrimino_queue.len()]
+        self.piece.get_next_tetrimino(index)
     }
 
     pub fn get_tetrimino_queue_length(&self) -> usize {
-        self.tetrimino_queue.len()
+        self.piece.tetrimino_queue.len()
     }
 
     pub fn get_tetrimino_types(&self) -> &[TetriminoType] {
@@ -77,8 +428,9 @@ impl GameCore {
 
     // returns the old active tetrimino
     pub fn set_active_tetrimino(&mut self, active_tetrimino: ActiveTetrimino) -> ActiveTetrimino {
-        let old_active_tetrimino = std::mem::replace(&mut self.active_tetrimino, active_tetrimino);
+        let old_active_tetrimino = std::mem::replace(&mut self.piece.active_tetrimino, active_tetrimino);
         self.active_tetrimino_updated();
+        self.piece.last_move_rotated = false;
 
         old_active_tetrimino
     }
@@ -86,45 +438,112 @@ impl GameCore {
     // returns the old active tetrimino
     pub fn next_tetrimino(&mut self) -> ActiveTetrimino {
         let new_tetrimino = self.tetrimino_generator.next();
-        let next_tetrimino = std::mem::replace(&mut self.tetrimino_queue[self.next_tetrimino_index], new_tetrimino);
-        self.next_tetrimino_index = (self.next_tetrimino_index + 1) % self.tetrimino_queue.len();
-        let old_active_tetrimino = self.set_active_tetrimino(next_tetrimino.as_active_instance(self.board.get_spawn_point()));
+        let next_tetrimino = std::mem::replace(&mut self.piece.tetrimino_queue[self.piece.next_tetrimino_index], new_tetrimino);
+        self.piece.next_tetrimino_index = (self.piece.next_tetrimino_index + 1) % self.piece.tetrimino_queue.len();
+        let old_active_tetrimino = self.set_active_tetrimino(self.spawn(next_tetrimino));
 
         old_active_tetrimino
     }
 
+    /**
+     * Replaces the value of every cell on the board, as well as in every piece's active, held
+     * and queued tetriminos, starting with the selected piece.
+     */
+    pub fn map_cell_values(&mut self, mut f: impl FnMut(u32) -> u32) {
+        self.board.map_cells(&mut f);
+
+        self.piece.map_cell_values(&mut f);
+        for piece in self.parked_pieces.iter_mut().flatten() {
+            piece.map_cell_values(&mut f);
+        }
+    }
+
+    /**
+     * Throws away the active, held and queued tetriminos, and draws new ones starting from a
+     * fresh bag that the given tetrimino types were already drawn from.
+     */
+    pub fn restart_queue(&mut self, drawn: &[usize]) {
+        self.tetrimino_generator.start_bag_without(drawn);
+        self.piece.held_tetrimino = None;
+
+        let active_tetrimino = self.tetrimino_generator.next();
+        for i in 0..self.piece.tetrimino_queue.len() {
+            self.piece.tetrimino_queue[i] = self.tetrimino_generator.next();
+        }
+        self.piece.next_tetrimino_index = 0;
+
+        self.set_active_tetrimino(self.spawn(active_tetrimino));
+    }
+
+    pub fn set_next_identity(&mut self, next_identity: u32) {
+        self.tetrimino_generator.set_next_identity(next_identity);
+    }
+
+    pub fn get_last_placement(&self) -> Placement {
+        self.piece.last_placement
+    }
+
+    /**
+     * A t-spin is a t tetrimino placed right after rotating, with at least three of the four
+     * corners around its center filled.  The walls and floor count as filled corners.  This is
+     * the only t-spin rule, which every mode reads back from the last placement.
+     */
+    fn is_active_tetrimino_t_spun(&self) -> bool {
+        let active_tetrimino = &self.piece.active_tetrimino;
+        let is_t_tetrimino = active_tetrimino.get_tetrimino().tetrimino_type.get_points() == defaults::tetriminos::T_TETRIMINO.get_points();
+        if !self.piece.last_move_rotated || !is_t_tetrimino {
+            return false
+        }
+
+        // the center is the only block with three neighbouring blocks, a block being piece_scale cells across
+        let scale = Point(self.piece_scale, self.piece_scale);
+        let points = active_tetrimino.get_points();
+        let center = points.iter().cloned().find(|&p| {
+            [Point(0, 1), Point(0, -1), Point(1, 0), Point(-1, 0)]
+                .iter()
+                .filter(|&&o| points.contains(&(p + o * scale)))
+                .count() == 3
+        });
+
+        center.is_some_and(|center| {
+            [Point(1, 1), Point(1, -1), Point(-1, 1), Point(-1, -1)]
+                .iter()
+                .filter(|&&o| !self.do_points_fit(vec![center + o * scale]))
+                .count() >= 3
+        })
+    }
+
     pub fn active_tetrimino_updated(&mut self) {
-        let points = self.active_tetrimino.get_points();
-        let ghost_translation = self.board.first_collision(points);
-        self.ghost_tetrimino = self.active_tetrimino.get_translated_points(ghost_translation);
+        let points = self.piece.active_tetrimino.get_points();
+        let ghost_translation = self.first_collision(points);
+        self.piece.ghost_tetrimino = self.piece.active_tetrimino.get_translated_points(ghost_translation);
     }
 
     pub fn hold(&mut self) {
-        match &self.held_tetrimino {
+        match &self.piece.held_tetrimino {
             Some(_) => {
-                let held_tetrimino = self.held_tetrimino
-                    .take()
-                    .unwrap()
-                    .as_active_instance(self.board.get_spawn_point());
+                let held_tetrimino = self.piece.held_tetrimino.take().unwrap();
+                let held_tetrimino = self.spawn(held_tetrimino);
                 let old_tetrimino = self.set_active_tetrimino(held_tetrimino).tetrimino;
 
-                self.held_tetrimino = Some(old_tetrimino)
+                self.piece.held_tetrimino = Some(old_tetrimino)
             },
             None => {
-                self.held_tetrimino = Some(self.next_tetrimino().tetrimino)                
+                self.piece.held_tetrimino = Some(self.next_tetrimino().tetrimino)                
             }
         }
     }
 
     pub fn get_held(&self) -> Option<&Tetrimino> {
-        self.held_tetrimino.as_ref()
+        self.piece.get_held()
     }
 
     pub fn translate(&mut self, direction: Point) -> bool {
-        let translated_points = self.active_tetrimino.get_translated_points(direction);
-        if self.board.do_points_fit(translated_points) {
-            self.active_tetrimino.translate(direction);
+        let translated_points = self.piece.active_tetrimino.get_translated_points(direction);
+        if self.do_points_fit(translated_points) {
+            self.piece.active_tetrimino.translate(direction);
             self.active_tetrimino_updated();
+            self.piece.last_move_rotated = false;
 
             true
         } else {
@@ -132,8 +551,43 @@ impl GameCore {
         }
     }
 
+    /**
+     * Pushes garbage rows in beneath the stack, one for each set of holes.  The active
+     * tetriminos are carried up along with the stack.  Either every row rises or, if the stack
+     * or any active tetrimino would be pushed over the top of the board, none of them do and
+     * false is returned.
+     */
+    pub fn raise_garbage_rows(&mut self, rows: &[Vec<i32>]) -> bool {
+        let rise = Point::unit_y(rows.len() as i32);
+        let height = self.board.get_height();
+        let stack_fits = self.board.num_active_rows() + rows.len() < height;
+        let pieces_fit = self.get_pieces()
+            .flat_map(|piece| piece.active_tetrimino.get_translated_points(rise))
+            .all(|p| (p.y() as usize) < height);
+        if !stack_fits || !pieces_fit {
+            return false;
+        }
+
+        for holes in rows.iter() {
+            self.board.insert_garbage_row(holes);
+        }
+
+        self.piece.active_tetrimino.translate(rise);
+        for piece in self.parked_pieces.iter_mut().flatten() {
+            piece.active_tetrimino.translate(rise);
+        }
+        self.active_tetrimino_updated();
+
+        true
+    }
+
     pub fn add_tetrimino(&mut self) -> Option<Vec<i32>> {
-        let rows = self.board.add_tetrimino(self.active_tetrimino.clone());
+        self.piece.last_placement = Placement {
+            t_spin: self.is_active_tetrimino_t_spun(),
+        };
+        self.piece.last_move_rotated = false;
+
+        let rows = self.board.add_tetrimino(self.piece.active_tetrimino.clone());
         self.next_tetrimino();
 
         rows
@@ -141,47 +595,70 @@ impl GameCore {
 
     // returns true if the active tetrimino successfully fell, false otherwise
     pub fn try_fall(&mut self) -> bool {
-        self.translate(Point(0, -1))
+        self.translate(Point(0, -self.movement_step))
+    }
+
+    /**
+     * Checks whether the board itself stops the selected piece from falling.  A piece held up
+     * only by another player's piece is not resting, and can't be placed where it is.
+     */
+    pub fn is_resting_on_board(&self) -> bool {
+        let fallen_points = self.piece.active_tetrimino.get_translated_points(Point(0, -self.movement_step));
+        !self.board.do_points_fit(fallen_points)
     }
 
     pub fn fall(&mut self) -> (bool, Option<Vec<i32>>) {
         // if the piece can fall no further, then place it and get the next piece
-        if !self.try_fall() {
+        if !self.try_fall() && self.is_resting_on_board() {
             (true, self.add_tetrimino())
         } else {
             (false, None)
         }
     }
 
-    pub fn fastfall(&mut self) -> (i32, Option<Vec<i32>>) {
-        let translation = self.board.first_collision(self.active_tetrimino.get_points());
-        self.active_tetrimino = self.active_tetrimino.clone().translated(translation);
-        (-translation.y(), self.add_tetrimino())
+    /**
+     * Drops the piece as far as it goes, and places it if it landed on the board.  Returns how
+     * far the piece dropped, whether it was placed, and the rows it filled.
+     */
+    pub fn fastfall(&mut self) -> (i32, bool, Option<Vec<i32>>) {
+        let translation = self.first_collision(self.piece.active_tetrimino.get_points());
+        self.piece.active_tetrimino = self.piece.active_tetrimino.clone().translated(translation);
+        if translation.y() != 0 {
+            self.piece.last_move_rotated = false;
+        }
+        if !self.is_resting_on_board() {
+            // the piece landed on another piece, so it stays in play until that piece moves
+            self.active_tetrimino_updated();
+            return (-translation.y(), false, None)
+        }
+        (-translation.y(), true, self.add_tetrimino())
     }
 
     pub fn rotate(&mut self, direction: Direction) -> bool {
-        let oriented_points = self.active_tetrimino.get_rotated_points(direction);
+        let oriented_points = self.piece.active_tetrimino.get_rotated_points(direction);
 
-        if self.board.do_points_fit(oriented_points.clone()) {
-            self.active_tetrimino.rotate(direction);
+        if self.do_points_fit(oriented_points.clone()) {
+            self.piece.active_tetrimino.rotate(direction);
             self.active_tetrimino_updated();
+            self.piece.last_move_rotated = true;
 
             true
         } else {
-            let tetrimino = self.active_tetrimino.get_tetrimino().tetrimino_type;
+            let tetrimino = self.piece.active_tetrimino.get_tetrimino().tetrimino_type;
             let wall_kicks = tetrimino
-                .get_wall_kicks(self.active_tetrimino.orientation, direction);
+                .get_wall_kicks(self.piece.active_tetrimino.orientation, direction);
 
-            for wall_kick in wall_kicks.iter().cloned() {
+            for wall_kick in wall_kicks.iter().map(|&kick| kick * Point::diag(self.movement_step)) {
                 let translated = oriented_points
                     .iter()
                     .map(|p| *p + wall_kick)
                     .collect::<Vec<_>>();
 
-                if self.board.do_points_fit(translated) {
-                    self.active_tetrimino.translate(wall_kick);
-                    self.active_tetrimino.rotate(direction);
+                if self.do_points_fit(translated) {
+                    self.piece.active_tetrimino.translate(wall_kick);
+                    self.piece.active_tetrimino.rotate(direction);
                     self.active_tetrimino_updated();
+                    self.piece.last_move_rotated = true;
 
                     return true;
                 }
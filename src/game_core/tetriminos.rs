@@ -24,9 +24,24 @@ macro_rules! all_orientations {
 type CellValueType = u32;
 
 pub trait TetriminoGenerator {
+    fn box_clone(&self) -> Box<dyn TetriminoGenerator>;
+
     fn next(&mut self) -> Tetrimino;
     fn get_tetrimino_types(&self) -> &'static [TetriminoType];
     fn set_seed(&mut self, seed: Vec<u8>);
+    // generators that give each piece its own identity should continue numbering from the given identity
+    fn set_next_identity(&mut self, _next_identity: u32) {}
+    // generators that draw from bags should start a fresh bag, which the given tetrimino types were already drawn from
+    fn start_bag_without(&mut self, _drawn: &[usize]) {}
+    // how far the generator has gotten, so that a copy built elsewhere can continue from the same place
+    fn save_state(&self) -> serde_json::Value;
+    fn load_state(&mut self, state: &serde_json::Value);
+}
+
+impl Clone for Box<dyn TetriminoGenerator> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
 }
 
 #[derive(Debug)]
@@ -102,6 +117,8 @@ pub struct ActiveTetrimino {
     pub position: Point,
     pub orientation: Orientation,
     pub tetrimino: Tetrimino,
+    // each block of the tetrimino takes up scale x scale cells
+    pub scale: i32,
 }
 
 impl ActiveTetrimino {
@@ -110,6 +127,7 @@ impl ActiveTetrimino {
             position,
             orientation: Orientation::Origin,
             tetrimino,
+            scale: 1,
         }
     }
 
@@ -118,6 +136,11 @@ impl ActiveTetrimino {
         self
     }
 
+    pub fn scaled(mut self, scale: i32) -> Self {
+        self.scale = scale;
+        self
+    }
+
     pub fn translate(&mut self, direction: Point) {
         self.position = self.position + direction
     }
@@ -131,27 +154,39 @@ impl ActiveTetrimino {
     }
 
     pub fn get_translated_points(&self, translation: Point) -> Vec<Point> {
-        let tetrimino_type = self.tetrimino.tetrimino_type;
-        let translation = self.position - tetrimino_type.bounding_box + translation;
-
-        tetrimino_type.shapes[self.orientation as usize]
-            .iter()
-            .cloned()
-            .map(|p| p + translation)
-            .collect::<Vec<_>>()
+        self.get_oriented_points(self.orientation, translation)
     }
 
     pub fn get_rotated_points(&self, direction: Direction) -> Vec<Point> {
+        self.get_oriented_points(self.orientation.rotated(direction), Point(0, 0))
+    }
+
+    /**
+     * Calculates the cells covered by the tetrimino in the given orientation.  When the tetrimino
+     * is scaled up, each block expands into a square of cells, listed one block after another.
+     */
+    fn get_oriented_points(&self, orientation: Orientation, translation: Point) -> Vec<Point> {
         let tetrimino_type = self.tetrimino.tetrimino_type;
-        let translation = self.position - tetrimino_type.bounding_box;
+        let translation = self.position + translation;
+        let scale = self.scale;
 
-        tetrimino_type.shapes[self.orientation.rotated(direction) as usize]
+        tetrimino_type.shapes[orientation as usize]
             .iter()
             .cloned()
-            .map(|p| p + translation)
+            .flat_map(|p| {
+                let origin = (p - tetrimino_type.bounding_box) * Point::diag(scale) + translation;
+                (0..scale * scale).map(move |i| origin + Point(i % scale, i / scale))
+            })
             .collect::<Vec<_>>()
     }
 
+    /**
+     * Returns the value of the block covering the point at the given index of `get_points`.
+     */
+    pub fn get_point_value(&self, index: usize) -> CellValueType {
+        self.tetrimino.values[index / (self.scale * self.scale) as usize]
+    }
+
     pub fn get_tetrimino(&self) -> &Tetrimino {
         &self.tetrimino
     }
@@ -1,4 +1,7 @@
-#[derive(Copy, Clone, Eq, PartialEq)]
+use serde::{Serialize, Deserialize};
+
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Orientation {
     // 0 degree Orientation
     Origin,
@@ -1,5 +1,7 @@
 
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Point(pub i32, pub i32);
 
 impl Point {
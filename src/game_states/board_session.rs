@@ -0,0 +1,145 @@
+use macroquad::prelude::*;
+
+use crate::drivers::*;
+use crate::game_core::utils::point::Point;
+use crate::ui::game_widgets::widget::*;
+use crate::ui::rendering::*;
+use crate::ui::utils::board_transition_progress::BoardTransitionsProgress;
+
+
+/**
+ * What a single frame of a board session did.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionFrame {
+    // the game is over, and its last transition has played out
+    Finished,
+    // a transition is still playing out on the board
+    Transitioning,
+    // the board was settled, so the driver simulated a frame, and the frame's actions apply
+    Settled { piece_placed: bool },
+}
+
+/**
+ * The transition playing out on a board, which every game state steps through the same way.
+ * While a transition plays out, the board can't be acted upon, and once it has finished, the
+ * driver decides what happens next.
+ */
+#[derive(Clone)]
+pub struct BoardSession {
+    transition_durations: BoardTransitionsProgress,
+    transition_progress: BoardTransitionsProgress,
+    transition: BoardTransition,
+}
+
+impl BoardSession {
+    pub fn new() -> Self {
+        Self {
+            transition: BoardTransition::new(),
+            transition_durations: BoardTransitionsProgress::new(),
+            transition_progress: BoardTransitionsProgress::new()
+        }
+    }
+
+    pub fn set_transition(&mut self, transition: BoardTransition) {
+        self.transition = transition;
+
+        // make sure all arrays are sorted and deduped
+        self.transition.compress();
+        self.transition_progress = self.transition_durations.with_board_transition(&self.transition);
+    }
+
+    /**
+     * Actions are only performed while the board is settled.
+     */
+    pub fn is_inert(&self) -> bool {
+        self.transition.is_inert()
+    }
+
+    pub fn is_finished(&self, driver: &dyn Driver) -> bool {
+        driver.is_game_over() && self.transition.is_inert()
+    }
+
+    /**
+     * Simulates a single frame of the driver, or of the transition playing out on its board.
+     */
+    pub fn update(&mut self, driver: &mut dyn Driver) -> SessionFrame {
+        self.update_with(
+            driver,
+            &mut (),
+            |_, driver| driver.next_frame(),
+            |_, driver, transition| driver.finish_transition(transition))
+    }
+
+    /**
+     * The same as `update`, except that the driver's frames and transitions are run by something
+     * else that needs to see every one of them, such as a battle.
+     */
+    pub fn update_with<T>(
+        &mut self,
+        driver: &mut dyn Driver,
+        runner: &mut T,
+        next_frame: impl FnOnce(&mut T, &mut dyn Driver) -> BoardTransition,
+        finish_transition: impl FnOnce(&mut T, &mut dyn Driver, BoardTransition) -> BoardTransition,
+    ) -> SessionFrame {
+        if self.is_finished(driver) {
+            // the game is finished, so nothing is left to simulate
+            return SessionFrame::Finished
+        }
+
+        if self.transition.is_inert() {
+            let new_transition = next_frame(runner, driver);
+            self.set_transition(new_transition);
+
+            return SessionFrame::Settled {
+                piece_placed: self.transition.get_points_added().is_some(),
+            }
+        }
+
+        self.transition_progress.next_frame();
+        if self.transition_progress.is_complete() {
+            let transition = std::mem::replace(&mut self.transition, BoardTransition::new());
+            let new_transition = finish_transition(runner, driver, transition);
+            self.set_transition(new_transition);
+        }
+
+        SessionFrame::Transitioning
+    }
+
+    pub fn get_widget_state<'a>(&'a self, driver: &'a dyn Driver) -> WidgetState<'a> {
+        WidgetState {
+            driver,
+            transition: &self.transition,
+            transition_progress: self.transition_progress,
+        }
+    }
+
+    pub fn draw(&self, driver: &dyn Driver, widgets: &mut [Box<dyn Widget>], render_manager: &mut RenderManager) {
+        let widget_state = self.get_widget_state(driver);
+        for widget in widgets.iter_mut() {
+            widget.draw(widget_state, render_manager.get_rendering_state(widget_state));
+        }
+    }
+}
+
+/**
+ * Draws the end of game summary over the finished game, a line of text at a time.
+ */
+pub fn draw_summary(lines: Vec<(String, Color, f32)>) {
+    let dimensions = Point(400, 50 + 45 * lines.len() as i32);
+    let position = Point((screen_width() as i32 - dimensions.x()) / 2, (screen_height() as i32 - dimensions.y()) / 2);
+    draw_rectangle(
+        position.x() as f32,
+        position.y() as f32,
+        dimensions.x() as f32,
+        dimensions.y() as f32,
+        Color::new(0.0, 0.0, 0.0, 0.85),
+    );
+
+    for (i, (text, color, font_size)) in lines.into_iter().enumerate() {
+        let dim = measure_text(&text, None, font_size as u16, 1.0);
+        let x_pos = position.x() as f32 + (dimensions.x() as f32 - dim.width) / 2.0;
+        let y_pos = position.y() as f32 + 60.0 + 45.0 * i as f32;
+        draw_text(&text, x_pos, y_pos, font_size, color);
+    }
+}
@@ -0,0 +1,213 @@
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use super::*;
+use super::board_session::*;
+use super::menu_state::MenuState;
+use super::versus_state::{KeyMap, PLAYER_ONE_KEYS, PLAYER_TWO_KEYS};
+
+use crate::drivers::*;
+use crate::drivers::coop_driver::CoopDriver;
+use crate::game_core::GameCore;
+use crate::game_core::tetriminos::Tetrimino;
+use crate::game_core::utils::point::Point;
+use crate::ui::game_widgets::tetris_board::TetrisBoard;
+use crate::ui::game_widgets::tetrimino_display::TetriminoDisplay;
+use crate::ui::game_widgets::label::Label;
+use crate::ui::game_widgets::widget::*;
+use crate::ui::button::ButtonHandler;
+use crate::ui::rendering::*;
+
+
+const HOLD_DELAY: usize = 15;
+const HOLD_RATE: usize = 3;
+const FASTFALL_HOLD: usize = 10;
+
+type TetriminoExtractor = for <'b> fn(&'b GameCore) -> Option<&'b Tetrimino>;
+
+// the held and queued tetriminos of each player's piece
+const HELD_EXTRACTORS: [TetriminoExtractor; 2] = [
+    |core| core.get_piece(0).get_held(),
+    |core| core.get_piece(1).get_held(),
+];
+const QUEUE_EXTRACTORS: [[TetriminoExtractor; 3]; 2] = [
+    [
+        |core| Some(core.get_piece(0).get_next_tetrimino(0)),
+        |core| Some(core.get_piece(0).get_next_tetrimino(1)),
+        |core| Some(core.get_piece(0).get_next_tetrimino(2)),
+    ],
+    [
+        |core| Some(core.get_piece(1).get_next_tetrimino(0)),
+        |core| Some(core.get_piece(1).get_next_tetrimino(1)),
+        |core| Some(core.get_piece(1).get_next_tetrimino(2)),
+    ],
+];
+
+fn create_buttons(keys: &KeyMap) -> Vec<ButtonHandler<CoopState, ()>> {
+    let rotate_cc = ButtonHandler::pressable(keys.rotate_counterclockwise, |state: &mut CoopState| { state.driver.rotate_counterclockwise(); });
+    let rotate_c = ButtonHandler::pressable(keys.rotate_clockwise, |state: &mut CoopState| { state.driver.rotate_clockwise(); });
+    let hold = ButtonHandler::pressable(keys.hold, |state: &mut CoopState| { state.driver.hold(); });
+
+    let left = ButtonHandler::holdable(keys.translate_left, HOLD_DELAY, HOLD_RATE, |state: &mut CoopState| { state.driver.translate_left(); });
+    let right = ButtonHandler::holdable(keys.translate_right, HOLD_DELAY, HOLD_RATE, |state: &mut CoopState| { state.driver.translate_right(); });
+    let down = ButtonHandler::pressable(keys.soft_drop, |state: &mut CoopState| {
+        state.driver.start_fastfalling();
+    }).with_release_action(|state: &mut CoopState| {
+        state.driver.stop_fastfalling();
+    });
+    let fastfall = ButtonHandler::pressable(keys.hard_drop, |state: &mut CoopState| {
+        let player = state.driver.get_game_core().get_selected_piece();
+        if state.fastfall_delay[player] == 0 {
+            let new_transition = state.driver.fastfall();
+            state.session.set_transition(new_transition);
+        }
+    });
+
+    vec![rotate_cc, rotate_c, hold, left, right, down, fastfall]
+}
+
+/**
+ * Two players on one shared board, each with their own piece and keys.
+ */
+pub struct CoopState {
+    driver: Box<CoopDriver>,
+
+    render_manager: RenderManager,
+    widgets: Vec<Box<dyn Widget>>,
+    // the buttons of each player, whose piece is selected while they are updated
+    buttons: Vec<Vec<ButtonHandler<Self, ()>>>,
+    // each player's hard drop is held off briefly after their own piece is placed
+    fastfall_delay: [usize; 2],
+
+    session: BoardSession,
+}
+
+impl CoopState {
+    pub fn new(driver: Box<CoopDriver>, render_manager: RenderManager) -> Self {
+        let board = driver.get_game_core().get_board();
+        let board_height = (screen_height() * 0.8) as i32;
+        let board_dimensions = Point(board_height * board.get_width() as i32 / board.get_height() as i32, 10 + board_height);
+        let board_position = Point((screen_width() as i32 - board_dimensions.x()) / 2, (screen_height() as i32 - board_dimensions.y()) / 2);
+        let tetris_board = TetrisBoard::new((board_position, board_position + board_dimensions));
+
+        let mut widgets: Vec<Box<dyn Widget>> = Vec::new();
+        widgets.push(Box::new(tetris_board));
+
+        /*
+         * The first player's hold and queue are on the left of the board, and the second
+         * player's are on the right.
+         */
+        let tetrimino_display_dimensions = Point(60, 60);
+        let display_x = [
+            board_position.x() - tetrimino_display_dimensions.x() - 20,
+            board_position.x() + board_dimensions.x() + 20,
+        ];
+        for player in 0..2 {
+            let hold_position = Point(display_x[player], board_position.y() + 20);
+            widgets.push(Box::new(TetriminoDisplay::new(
+                (hold_position, hold_position + tetrimino_display_dimensions),
+                driver.get_game_core(),
+                HELD_EXTRACTORS[player])));
+
+            let queue_length = driver.get_game_core().get_tetrimino_queue_length();
+            for (i, &extractor) in QUEUE_EXTRACTORS[player].iter().take(queue_length).enumerate() {
+                let position = hold_position + Point(0, 120 + 80 * i as i32);
+                widgets.push(Box::new(TetriminoDisplay::new(
+                    (position, position + tetrimino_display_dimensions),
+                    driver.get_game_core(),
+                    extractor)));
+            }
+        }
+
+        let score_position = board_position + Point(0, board_dimensions.y() + 30);
+        widgets.push(Box::new(Label::new(
+            score_position,
+            RED,
+            28.0,
+            |driver| format!("score: {}    lines: {}", driver.get_score(), driver.get_lines_cleared()))));
+
+        Self {
+            driver,
+
+            render_manager,
+            widgets,
+            buttons: vec![create_buttons(&PLAYER_ONE_KEYS), create_buttons(&PLAYER_TWO_KEYS)],
+            fastfall_delay: [0; 2],
+
+            session: BoardSession::new(),
+        }
+    }
+
+    /**
+     * Draws the end of game summary over the finished game.
+     */
+    fn draw_summary(&self) {
+        draw_summary(vec![
+            ("game over".to_string(), RED, 48.0),
+            (format!("score: {}", self.driver.get_score()), ORANGE, 32.0),
+            ("press enter to continue".to_string(), WHITE, 24.0),
+        ]);
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for CoopState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::P) {
+                gamestate_manager.get_gamestate_stack().push(self);
+                gamestate_manager.get_gamestate_stack().push(MenuState::new(Vec::new()).await.boxed());
+                return;
+            }
+
+            if self.session.is_finished(self.driver.as_ref()) && is_key_pressed(KeyCode::Enter) {
+                return;
+            }
+
+            for delay in self.fastfall_delay.iter_mut() {
+                *delay = delay.saturating_sub(1);
+            }
+
+            if let SessionFrame::Settled { piece_placed } = self.session.update(self.driver.as_mut()) {
+                // the placed piece stays selected, so it tells whose piece was placed
+                if piece_placed {
+                    let player = self.driver.get_game_core().get_selected_piece();
+                    self.fastfall_delay[player] = FASTFALL_HOLD;
+                }
+
+                // a placed piece must stay selected until its transition has finished
+                if self.session.is_inert() {
+                    let mut buttons = std::mem::take(&mut self.buttons);
+                    for (player, player_buttons) in buttons.iter_mut().enumerate() {
+                        self.driver.select_player(player);
+                        for button in player_buttons.iter_mut() {
+                            button.update(self.as_mut());
+                        }
+
+                        if !self.session.is_inert() {
+                            break;
+                        }
+                    }
+                    self.buttons = buttons;
+                }
+            }
+
+            self.session.draw(self.driver.as_ref(), &mut self.widgets, &mut self.render_manager);
+
+            if self.session.is_finished(self.driver.as_ref()) {
+                self.draw_summary();
+            }
+
+            next_frame().await;
+        }
+    }
+}
+
+pub async fn construct_coop_gamestate<'a>(factory: &mut GameStateManager<'a>) -> Box<dyn GameState<'a> + 'a> {
+    CoopState::new(
+        DriverBuilder::<CoopDriver>::new().build_boxed(),
+        factory.get_render_manager_factory().start_building().build().await
+    ).boxed()
+}
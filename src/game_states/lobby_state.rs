@@ -0,0 +1,81 @@
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use super::*;
+use super::netplay_state::*;
+
+use crate::netplay::lobby::*;
+
+
+/**
+ * Lists the games being hosted on the network, any of which can be joined.
+ */
+pub struct LobbyState {
+    browser: LobbyBrowser,
+    selected_game: usize,
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for LobbyState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            self.browser.update();
+            let num_games = self.browser.get_games().len();
+            self.selected_game = std::cmp::min(self.selected_game, num_games.saturating_sub(1));
+
+            if num_games > 0 {
+                if is_key_pressed(KeyCode::Up) {
+                    self.selected_game = (self.selected_game + num_games - 1) % num_games;
+                }
+                if is_key_pressed(KeyCode::Down) {
+                    self.selected_game = (self.selected_game + 1) % num_games;
+                }
+                if is_key_pressed(KeyCode::Enter) {
+                    let game = &self.browser.get_games()[self.selected_game];
+                    let state = ConnectingState::new(game.address.to_string(), game.announcement.port);
+
+                    // coming back from the game returns to the lobby
+                    gamestate_manager.get_gamestate_stack().push(self);
+                    gamestate_manager.get_gamestate_stack().push(state.boxed());
+                    return;
+                }
+            }
+
+            draw_centered_text(&format!("games on port {}", LOBBY_PORT), -120.0, 40.0, WHITE);
+            if let Some(e) = self.browser.get_error() {
+                draw_centered_text(&format!("could not listen for games: {}", e), -60.0, 24.0, RED);
+            } else if num_games == 0 {
+                draw_centered_text("looking for games...", -60.0, 32.0, GRAY);
+            }
+
+            for (i, game) in self.browser.get_games().iter().enumerate() {
+                let announcement = &game.announcement;
+                let text = format!("{}'s {} ({}/{}) at {}",
+                    announcement.name, announcement.mode, announcement.players, announcement.max_players, game.address);
+                let (font_size, color) = if i == self.selected_game {
+                    (40.0, YELLOW)
+                } else {
+                    (32.0, WHITE)
+                };
+                draw_centered_text(&text, -60.0 + 40.0 * i as f32, font_size, color);
+            }
+
+            draw_centered_text("enter to join, escape to go back", screen_height() / 2.0 - 40.0, 24.0, GRAY);
+
+            next_frame().await;
+        }
+    }
+}
+
+pub async fn construct_lobby_gamestate<'a>() -> Box<dyn GameState<'a> + 'a> {
+    LobbyState {
+        browser: LobbyBrowser::new(),
+        selected_game: 0,
+    }.boxed()
+}
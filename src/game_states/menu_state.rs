@@ -86,9 +86,15 @@ impl<'a> GameState<'a> for MenuState<'a> {
             const DEFAULT_OPTION_FONT: f32 = 32.0;
             let dim = measure_text("A", None, DEFAULT_OPTION_FONT as u16, 1.0);
             let option_height = dim.height + dim.offset_y;
-            for (i, option) in self.options.iter().enumerate() {
+
+            // only the options that fit on screen are drawn, scrolling along with the selection
+            let num_visible = std::cmp::max(1, ((screen_height() / 2.0 - option_height) / option_height) as usize);
+            let first_visible = std::cmp::min(
+                self.selected_option.saturating_sub(num_visible / 2),
+                self.options.len().saturating_sub(num_visible));
+            for (i, option) in self.options.iter().enumerate().skip(first_visible).take(num_visible) {
                 let mut font_size = DEFAULT_OPTION_FONT;
-                let mut color = colors[i];
+                let mut color = colors[i % colors.len()];
                 if self.selected_option == i {
                     font_size *= 1.25;
                 } else {
@@ -97,7 +103,7 @@ impl<'a> GameState<'a> for MenuState<'a> {
     
                 let dim = measure_text(&option.title, None, font_size as u16, 1.0);
                 let x_pos = (screen_width() - dim.width) / 2.0;
-                let y_pos = (screen_height() / 2.0) + option_height * i as f32;
+                let y_pos = (screen_height() / 2.0) + option_height * (i - first_visible) as f32;
                 draw_text(&option.title, x_pos, y_pos, font_size as f32, color);
             }
     
@@ -1,7 +1,15 @@
 use async_trait::async_trait;
 
+pub mod board_session;
+pub mod coop_state;
+pub mod lobby_state;
 pub mod menu_state;
+pub mod netplay_state;
+pub mod puzzle_menu;
+pub mod set_state;
 pub mod tetris_state;
+pub mod versus_replay;
+pub mod versus_state;
 
 use crate::ui::rendering::RenderManagerFactory;
 
@@ -0,0 +1,395 @@
+use std::time::Duration;
+
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use super::*;
+use super::menu_state::*;
+use super::versus_state::*;
+
+use crate::battle::Battle;
+use crate::drivers::utils::tetrimino_chooser::generate_rng_seed;
+use crate::game_core::utils::point::Point;
+use crate::netplay::*;
+use crate::netplay::connection::*;
+use crate::netplay::latency_shim::*;
+use crate::netplay::lobby::*;
+use crate::netplay::rollback::*;
+use crate::netplay::protocol::Message;
+use crate::debugging::drivers::recording::Action;
+use crate::ui::button::ButtonHandler;
+use crate::ui::rendering::*;
+
+
+const DEFAULT_HOST: &str = "localhost";
+const MAX_HOST_LENGTH: usize = 64;
+const SHIM_LATENCY: Duration = Duration::from_millis(100);
+const SHIM_JITTER: Duration = Duration::from_millis(50);
+
+/**
+ * Draws a line of text in the middle of the screen, for the states waiting on the network.
+ */
+pub fn draw_centered_text(text: &str, y_offset: f32, font_size: f32, color: Color) {
+    let dim = measure_text(text, None, font_size as u16, 1.0);
+    draw_text(text, (screen_width() - dim.width) / 2.0, screen_height() / 2.0 + y_offset, font_size, color);
+}
+
+/**
+ * Both players' games, along with the battle between them, which the rollback simulates.
+ */
+pub struct VersusSimulation {
+    battle: Battle,
+    players: Vec<VersusPlayer>,
+}
+
+impl VersusSimulation {
+    /**
+     * Both players' boards are laid out side by side, splitting the given dimensions in half.
+     */
+    pub fn new(mode: &NetplayMode, rng_seed: Vec<u8>, dimensions: Point) -> Self {
+        let area_width = dimensions.x() / 2;
+        let players = (0..2)
+            .map(|i| {
+                let area = (Point(area_width * i as i32, 0), Point(area_width * (i as i32 + 1), dimensions.y()));
+                VersusPlayer::new(i, (mode.get_driver)(rng_seed.clone()), area)
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            battle: Battle::new(players.len(), &rng_seed),
+            players,
+        }
+    }
+}
+
+impl Simulation for VersusSimulation {
+    type Snapshot = (Battle, Vec<VersusPlayerSnapshot>);
+
+    fn take_snapshot(&self) -> Self::Snapshot {
+        (self.battle.clone(), self.players.iter().map(|player| player.take_snapshot()).collect())
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &Self::Snapshot) {
+        self.battle = snapshot.0.clone();
+        for (player, player_snapshot) in self.players.iter_mut().zip(snapshot.1.iter()) {
+            player.restore_snapshot(player_snapshot);
+        }
+    }
+
+    fn simulate_frame(&mut self, actions: &[Vec<Action>]) {
+        for (player, actions) in self.players.iter_mut().zip(actions.iter()) {
+            player.update(&mut self.battle, actions);
+        }
+        end_match_if_decided(&self.battle, &mut self.players);
+    }
+
+    fn hash_state(&self) -> u64 {
+        let drivers = self.players.iter().map(|player| player.get_driver()).collect::<Vec<_>>();
+        hash_battle(&self.battle, &drivers)
+    }
+
+    fn get_lines_sent(&self, player: usize) -> usize {
+        self.battle.get_lines_sent(player)
+    }
+}
+
+/**
+ * A versus match against a player on another machine.  Both sides simulate both boards, and
+ * only the players' actions are sent over the network.
+ */
+pub struct NetplayState {
+    rollback: Rollback<VersusSimulation>,
+    simulation: VersusSimulation,
+    buttons: Vec<ButtonHandler<Vec<Action>, ()>>,
+    render_manager: RenderManager,
+}
+
+impl NetplayState {
+    pub fn new(connection: Connection, local_player: usize, mode: &NetplayMode, rng_seed: Vec<u8>, render_manager: RenderManager) -> Self {
+        let simulation = VersusSimulation::new(mode, rng_seed, Point(screen_width() as i32, screen_height() as i32));
+
+        Self {
+            rollback: Rollback::new(connection, local_player, simulation.players.len()),
+            simulation,
+            buttons: create_action_buttons(&PLAYER_ONE_KEYS),
+            render_manager,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for NetplayState {
+    async fn run(mut self: Box<Self>, _gamestate_manager: &mut GameStateManager) {
+        loop {
+            clear_background(BLACK);
+
+            let finished = self.simulation.players.iter().all(|player| player.is_finished());
+            if (finished || !self.rollback.is_running()) && is_key_pressed(KeyCode::Enter) {
+                self.rollback.disconnect();
+                return;
+            }
+
+            // local keys are dropped while waiting on the other side, rather than piling up
+            let actions = if self.rollback.is_stalled() {
+                Vec::new()
+            } else {
+                poll_action_buttons(&mut self.buttons)
+            };
+            self.rollback.update(&mut self.simulation, actions);
+
+            // by now any rollback has already been simulated back up to the present
+            for player in self.simulation.players.iter_mut() {
+                player.draw(&mut self.render_manager, &self.simulation.battle);
+            }
+
+            let label_x = screen_width() / 2.0 * self.rollback.get_local_player() as f32 + 20.0;
+            draw_text("you", label_x, 30.0, 32.0, YELLOW);
+
+            if finished {
+                draw_message_box(&get_match_result(&self.simulation.players), RED);
+            } else {
+                match self.rollback.get_status() {
+                    RollbackStatus::Running => {
+                        if self.rollback.is_stalled() {
+                            draw_centered_text("waiting for the other player...", 0.0, 32.0, WHITE);
+                        }
+                    },
+                    RollbackStatus::Disconnected(_) => draw_message_box("disconnected", RED),
+                    RollbackStatus::Desynced(_) => draw_message_box("desynced", RED),
+                }
+            }
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Waits for another player to connect, then starts the match with the chosen mode.
+ */
+pub struct HostingState {
+    host: std::io::Result<Host>,
+    mode: NetplayMode,
+    // lets players in the lobby find the game
+    advertiser: Advertiser,
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for HostingState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            self.advertiser.update();
+            let host = match &self.host {
+                Ok(host) => host,
+                Err(e) => {
+                    draw_centered_text(&format!("could not host: {}", e), 0.0, 32.0, RED);
+                    next_frame().await;
+                    continue;
+                }
+            };
+
+            match host.accept() {
+                Ok(Some(mut connection)) => {
+                    let rng_seed = generate_rng_seed();
+                    let hello = Message::Hello { mode: self.mode.name.to_string(), rng_seed: rng_seed.clone() };
+                    if connection.send(&hello).is_ok() {
+                        let render_manager = gamestate_manager.get_render_manager_factory().start_building().build().await;
+                        let state = NetplayState::new(connection, 0, &self.mode, rng_seed, render_manager);
+                        gamestate_manager.get_gamestate_stack().push(state.boxed());
+                        return;
+                    }
+                },
+                Ok(None) => (),
+                Err(e) => draw_centered_text(&format!("could not accept: {}", e), 50.0, 24.0, RED),
+            }
+
+            draw_centered_text(&format!("hosting {} on port {}", self.mode.name, NETPLAY_PORT), 0.0, 32.0, WHITE);
+            draw_centered_text("waiting for a player to connect, escape to cancel", 40.0, 24.0, GRAY);
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Keeps trying to connect to a host, then starts whichever match the host chose.
+ */
+pub struct ConnectingState {
+    host: String,
+    port: u16,
+    connection: Option<Connection>,
+    frames_until_retry: usize,
+}
+
+impl ConnectingState {
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            connection: None,
+            frames_until_retry: 0,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for ConnectingState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            match self.connection.as_mut() {
+                None => {
+                    if self.frames_until_retry == 0 {
+                        self.connection = Connection::connect(&self.host, self.port).ok();
+                        self.frames_until_retry = 60;
+                    } else {
+                        self.frames_until_retry -= 1;
+                    }
+                },
+                Some(connection) => match connection.receive() {
+                    // the host always says hello before anything else
+                    Ok(Some(Message::Hello { mode, rng_seed })) => {
+                        let mode = match find_netplay_mode(&mode) {
+                            Some(mode) => mode,
+                            None => return,
+                        };
+
+                        let render_manager = gamestate_manager.get_render_manager_factory().start_building().build().await;
+                        let connection = self.connection.take().unwrap();
+                        let state = NetplayState::new(connection, 1, &mode, rng_seed, render_manager);
+                        gamestate_manager.get_gamestate_stack().push(state.boxed());
+                        return;
+                    },
+                    Ok(_) => (),
+                    Err(_) => self.connection = None,
+                },
+            }
+
+            draw_centered_text(&format!("connecting to {}:{}", self.host, self.port), 0.0, 32.0, WHITE);
+            draw_centered_text("escape to cancel", 40.0, 24.0, GRAY);
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Lets the player type in the address of the host to connect to.
+ */
+pub struct HostEntryState {
+    host: String,
+    // connects through the latency shim, to test rollback on a slow network
+    through_shim: bool,
+    buttons: Vec<ButtonHandler<Self, bool>>,
+}
+
+impl HostEntryState {
+    pub fn new(through_shim: bool) -> Self {
+        let connect = ButtonHandler::pressable(KeyCode::Enter, |_: &mut Self| true);
+        let erase = ButtonHandler::holdable(KeyCode::Backspace, 20, 2, |state: &mut Self| {
+            state.host.pop();
+            false
+        });
+
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            through_shim,
+            buttons: vec![connect, erase],
+        }
+    }
+
+    /**
+     * Connects to the host, optionally through the latency shim, which runs on this machine.
+     */
+    fn construct_connecting_gamestate<'a>(&self) -> Box<dyn GameState<'a> + 'a> {
+        if self.through_shim && start_latency_shim(&self.host, SHIM_LATENCY, SHIM_JITTER).is_ok() {
+            ConnectingState::new("127.0.0.1".to_string(), SHIM_PORT).boxed()
+        } else {
+            ConnectingState::new(self.host.clone(), NETPLAY_PORT).boxed()
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for HostEntryState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            while let Some(character) = get_char_pressed() {
+                if character.is_ascii_graphic() && self.host.len() < MAX_HOST_LENGTH {
+                    self.host.push(character);
+                }
+            }
+
+            let mut buttons = std::mem::take(&mut self.buttons);
+            let connect = buttons.iter_mut().any(|button| button.update(self.as_mut()) == Some(true));
+            self.buttons = buttons;
+
+            if connect && !self.host.is_empty() {
+                let state = self.construct_connecting_gamestate();
+                gamestate_manager.get_gamestate_stack().push(state);
+                return;
+            }
+
+            draw_centered_text("host to connect to:", -40.0, 32.0, GRAY);
+            draw_centered_text(&format!("{}_", self.host), 0.0, 32.0, WHITE);
+            draw_centered_text("enter to connect, escape to cancel", 40.0, 24.0, GRAY);
+
+            next_frame().await;
+        }
+    }
+}
+
+async fn construct_hosting_gamestate<'a>(mode: NetplayMode) -> Box<dyn GameState<'a> + 'a> {
+    HostingState {
+        host: Host::new(NETPLAY_PORT),
+        advertiser: Advertiser::new(Announcement::new(mode.name, 2, NETPLAY_PORT)),
+        mode,
+    }.boxed()
+}
+
+/**
+ * Lets the player either host a match in one of the network modes, or connect to one.
+ */
+pub async fn get_netplay_menu<'a>() -> Box<dyn GameState<'a> + 'a> {
+    let mut menu_options = get_netplay_modes()
+        .into_iter()
+        .map(|mode| MenuOption::new(
+            format!("host {}", mode.name),
+            move |_| Box::pin(construct_hosting_gamestate(mode.clone()))
+        ))
+        .collect::<Vec<_>>();
+
+    menu_options.push(
+        MenuOption::new("lobby".to_string(), |_| {
+            Box::pin(super::lobby_state::construct_lobby_gamestate())
+        }));
+
+    menu_options.push(
+        MenuOption::new("connect".to_string(), |_| {
+            Box::pin(async { HostEntryState::new(false).boxed() })
+        }));
+
+    menu_options.push(
+        MenuOption::new("connect with latency".to_string(), |_| {
+            Box::pin(async { HostEntryState::new(true).boxed() })
+        }));
+
+    Box::new(MenuState::new(menu_options).await)
+}
@@ -0,0 +1,40 @@
+use crate::drivers::*;
+use crate::drivers::puzzle_driver::*;
+use crate::game_states::menu_state::*;
+use crate::game_states::tetris_state::TetrisState;
+use super::*;
+
+
+async fn construct_puzzle_gamestate<'a>(factory: &mut GameStateManager<'a>, puzzle: Puzzle) -> Box<dyn GameState<'a> + 'a> {
+    TetrisState::new(
+        DriverBuilder::<PuzzleDriver>::new().with_puzzle(puzzle).build_boxed(),
+        factory.get_render_manager_factory().start_building().build().await
+    ).boxed()
+}
+
+async fn get_puzzle_menu<'a>(pack: PuzzlePack) -> Box<dyn GameState<'a> + 'a> {
+    let menu_options = pack.puzzles
+        .into_iter()
+        .map(|puzzle| MenuOption::new(
+            puzzle.name.clone(),
+            move |f| Box::pin(construct_puzzle_gamestate(f, puzzle.clone()))
+        ))
+        .collect::<Vec<_>>();
+
+    Box::new(MenuState::new(menu_options).await)
+}
+
+/**
+ * Lists every puzzle pack, each of which opens a menu of its puzzles.
+ */
+pub async fn get_puzzle_pack_menu<'a>() -> Box<dyn GameState<'a> + 'a> {
+    let menu_options = PuzzlePack::load_all()
+        .into_iter()
+        .map(|pack| MenuOption::new(
+            pack.name.clone(),
+            move |_| Box::pin(get_puzzle_menu(pack.clone()))
+        ))
+        .collect::<Vec<_>>();
+
+    Box::new(MenuState::new(menu_options).await)
+}
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use super::*;
+use super::menu_state::*;
+use super::versus_state::*;
+
+use crate::drivers::utils::tetrimino_chooser::generate_rng_seed;
+use crate::ui::button::ButtonHandler;
+
+
+/**
+ * A round that has been played in the current set.
+ */
+struct PlayedRound {
+    // the local players in each seat
+    seats: [usize; 2],
+    winner: Option<usize>,
+    // the name the round's replay was saved under, or why it couldn't be
+    replay: Result<String, String>,
+}
+
+/**
+ * Plays versus rounds as a set, which the first player to win enough rounds takes.  With more
+ * than two local players, the loser of each set makes way for the next player in line, while
+ * the winner stays on.
+ */
+pub struct SetState {
+    wins_needed: usize,
+    // the local players sitting at each side of the screen
+    seats: [usize; 2],
+    // the local players waiting for their turn, who only exist with winner stays on
+    waiting: VecDeque<usize>,
+    wins: [usize; 2],
+    rounds: Vec<PlayedRound>,
+    // filled in by each round once it's over
+    round_result: Rc<RefCell<Option<RoundResult>>>,
+}
+
+impl SetState {
+    pub fn new(wins_needed: usize, num_players: usize) -> Self {
+        Self {
+            wins_needed,
+            seats: [0, 1],
+            waiting: (2..num_players).collect(),
+            wins: [0, 0],
+            rounds: Vec::new(),
+            round_result: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn get_player_name(player: usize) -> String {
+        format!("player {}", player + 1)
+    }
+
+    fn get_set_winner(&self) -> Option<usize> {
+        (0..2).find(|&seat| self.wins[seat] >= self.wins_needed)
+    }
+
+    /**
+     * Scores the round that just finished, and saves its replay.
+     */
+    fn record_round(&mut self, result: RoundResult) {
+        if let Some(seat) = result.winner {
+            self.wins[seat] += 1;
+        }
+
+        self.rounds.push(PlayedRound {
+            seats: self.seats,
+            winner: result.winner,
+            replay: result.replay,
+        });
+    }
+
+    /**
+     * Starts the next set, which is a rematch unless there is a player waiting to take the
+     * loser's seat.
+     */
+    fn start_next_set(&mut self) {
+        if let Some(winner) = self.get_set_winner() {
+            if let Some(challenger) = self.waiting.pop_front() {
+                let loser = 1 - winner;
+                self.waiting.push_back(self.seats[loser]);
+                self.seats[loser] = challenger;
+            }
+        }
+
+        self.wins = [0, 0];
+        self.rounds.clear();
+    }
+
+    fn draw_scoreboard(&self) {
+        let mut lines = vec![
+            (format!("first to {}", self.wins_needed), WHITE, 32.0),
+            (format!("{} {} - {} {}",
+                Self::get_player_name(self.seats[0]), self.wins[0], self.wins[1], Self::get_player_name(self.seats[1])), YELLOW, 48.0),
+        ];
+
+        for (i, round) in self.rounds.iter().enumerate() {
+            let result = match round.winner {
+                Some(seat) => format!("{} beat {}",
+                    Self::get_player_name(round.seats[seat]), Self::get_player_name(round.seats[1 - seat])),
+                None => "draw".to_string(),
+            };
+            let replay = match &round.replay {
+                Ok(name) => format!("saved as {}", name),
+                Err(e) => format!("not saved: {}", e),
+            };
+            lines.push((format!("round {}: {}, {}", i + 1, result, replay), GRAY, 24.0));
+        }
+
+        if !self.waiting.is_empty() {
+            let waiting = self.waiting.iter().map(|&player| Self::get_player_name(player)).collect::<Vec<_>>();
+            lines.push((format!("next up: {}", waiting.join(", ")), SKYBLUE, 24.0));
+        }
+
+        let prompt = match self.get_set_winner() {
+            Some(seat) if self.waiting.is_empty() =>
+                format!("{} wins the set! enter for a rematch, escape to quit", Self::get_player_name(self.seats[seat])),
+            Some(seat) =>
+                format!("{} wins the set and stays on! enter for the next set, escape to quit", Self::get_player_name(self.seats[seat])),
+            None => format!("enter to start round {}, escape to quit", self.rounds.len() + 1),
+        };
+        lines.push((prompt, RED, 32.0));
+
+        let mut y_pos = 100.0;
+        for (text, color, font_size) in lines.iter() {
+            let dim = measure_text(text, None, *font_size as u16, 1.0);
+            draw_text(text, (screen_width() - dim.width) / 2.0, y_pos, *font_size, *color);
+            y_pos += font_size * 1.5;
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for SetState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        let result = self.round_result.borrow_mut().take();
+        if let Some(result) = result {
+            self.record_round(result);
+        }
+
+        // the enter that dismissed the round has to be released before it starts another
+        let mut enter_button = ButtonHandler::pressable(KeyCode::Enter, |_: &mut ()| ());
+
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            if enter_button.update(&mut ()).is_some() {
+                if self.get_set_winner().is_some() {
+                    self.start_next_set();
+                }
+
+                let player_names = self.seats.iter().map(|&player| Self::get_player_name(player)).collect();
+                let round = VersusState::new(
+                    generate_rng_seed(),
+                    gamestate_manager.get_render_manager_factory().start_building().build().await
+                )
+                    .with_player_names(player_names)
+                    .with_round_result(self.round_result.clone());
+
+                // the round comes back to the set once it's over
+                gamestate_manager.get_gamestate_stack().push(self);
+                gamestate_manager.get_gamestate_stack().push(round.boxed());
+                return;
+            }
+
+            self.draw_scoreboard();
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Lets the players choose how long a set lasts, and whether more than two of them take turns.
+ */
+pub async fn get_set_menu<'a>() -> Box<dyn GameState<'a> + 'a> {
+    let sets = [
+        ("best of 3", 2, 2),
+        ("best of 5", 3, 2),
+        ("winner stays on, 3 players", 2, 3),
+        ("winner stays on, 4 players", 2, 4),
+    ];
+
+    let mut menu_options = vec![
+        MenuOption::new("single round".to_string(), |f| {
+            Box::pin(construct_versus_gamestate(f))
+        })
+    ];
+
+    menu_options.extend(sets
+        .iter()
+        .map(|&(title, wins_needed, num_players)| MenuOption::new(
+            title.to_string(),
+            move |_| Box::pin(async move { SetState::new(wins_needed, num_players).boxed() })
+        )));
+
+    menu_options.push(
+        MenuOption::new("replays".to_string(), |_| {
+            Box::pin(get_versus_replay_menu())
+        }));
+
+    Box::new(MenuState::new(menu_options).await)
+}
@@ -2,25 +2,35 @@ use macroquad::prelude::*;
 use async_trait::async_trait;
 
 use super::*;
+use super::board_session::*;
 use super::menu_state::MenuState;
 
 use crate::drivers::*;
 use crate::game_core::GameCore;
+use crate::game_core::defaults::settings::FRAMES_PER_SECOND;
 use crate::game_core::tetriminos::Tetrimino;
 use crate::game_core::utils::point::Point;
 use crate::ui::game_widgets::tetris_board::TetrisBoard;
 use crate::ui::game_widgets::tetrimino_display::TetriminoDisplay;
 use crate::ui::game_widgets::label::Label;
+use crate::ui::game_widgets::status_display::StatusDisplay;
 use crate::ui::game_widgets::widget::*;
 use crate::ui::button::ButtonHandler;
 use crate::ui::rendering::*;
-use crate::ui::utils::board_transition_progress::BoardTransitionsProgress;
 
 
 const HOLD_DELAY: usize = 15;
 const HOLD_RATE: usize = 3;
 const FASTFALL_HOLD: usize = 10;
 
+/**
+ * Formats a number of frames as minutes:seconds.hundredths
+ */
+fn format_frames(frames: usize) -> String {
+    let hundredths = frames * 100 / FRAMES_PER_SECOND;
+    format!("{}:{:02}.{:02}", hundredths / 6000, (hundredths / 100) % 60, hundredths % 100)
+}
+
 pub struct TetrisState {
     driver: Box<dyn Driver>,
 
@@ -39,9 +49,7 @@ pub struct TetrisState {
      */
     fastfall_delay: (usize, usize),
 
-    transition_durations: BoardTransitionsProgress,
-    transition_progress: BoardTransitionsProgress,
-    transition: BoardTransition,
+    session: BoardSession,
 }
 
 impl TetrisState {
@@ -96,12 +104,46 @@ impl TetrisState {
         widgets.push(Box::new(score_display));
         widgets.push(Box::new(level_display));
 
+        // modes with extra objectives display them beneath the score and level
+        let mut hud_position = score_position + Point::unit_y(80);
+
+        // timed modes display a countdown of the remaining time
+        if driver.get_time_remaining().is_some() {
+            let countdown_display = Label::new(
+                hud_position,
+                YELLOW,
+                32.0,
+                |driver| format!("time: {}", format_frames(driver.get_time_remaining().unwrap_or(0))));
+            widgets.push(Box::new(countdown_display));
+            hud_position = hud_position + Point::unit_y(40);
+        }
+
+        // modes that start with garbage display how much of it is left, and how long digging has taken
+        if driver.get_game_core().get_board().num_garbage_rows() > 0 {
+            let garbage_display = Label::new(
+                hud_position,
+                GRAY,
+                32.0,
+                |driver| format!("garbage: {}", driver.get_game_core().get_board().num_garbage_rows()));
+            let time_display = Label::new(
+                hud_position + Point::unit_y(40),
+                YELLOW,
+                32.0,
+                |driver| format!("time: {}", format_frames(driver.get_frames_elapsed())));
+            widgets.push(Box::new(garbage_display));
+            widgets.push(Box::new(time_display));
+            hud_position = hud_position + Point::unit_y(80);
+        }
+
+        widgets.push(Box::new(StatusDisplay::new(hud_position, SKYBLUE, 28.0)));
+
         /*
          * Create all of the buttons.
          */
         let rotate_cc = ButtonHandler::pressable(KeyCode::A, |state: &mut TetrisState| { state.driver.rotate_counterclockwise(); });
         let rotate_c = ButtonHandler::pressable(KeyCode::D, |state: &mut TetrisState| { state.driver.rotate_clockwise(); });
         let hold = ButtonHandler::pressable(KeyCode::W, |state: &mut TetrisState| { state.driver.hold(); });
+        let zone = ButtonHandler::pressable(KeyCode::Space, |state: &mut TetrisState| { state.driver.activate_zone(); });
 
         let left = ButtonHandler::holdable(KeyCode::Left, HOLD_DELAY, HOLD_RATE, |state: &mut TetrisState| { state.driver.translate_left(); });
         let right = ButtonHandler::holdable(KeyCode::Right, HOLD_DELAY, HOLD_RATE, |state: &mut TetrisState| { state.driver.translate_right(); });
@@ -113,12 +155,12 @@ impl TetrisState {
         let fastfall = ButtonHandler::pressable(KeyCode::Up, |state: &mut TetrisState| {
             if state.fastfall_delay.0 == 0 {
                 let new_transition = state.driver.fastfall();
-                state.set_transition(new_transition);
+                state.session.set_transition(new_transition);
             }
         });
 
         let buttons = vec![
-            rotate_cc, rotate_c, hold,
+            rotate_cc, rotate_c, hold, zone,
             left, right, down, fastfall
         ];
     
@@ -132,15 +174,28 @@ impl TetrisState {
 
             fastfall_delay: (0, FASTFALL_HOLD),
 
-            transition: BoardTransition::new(),
-            transition_durations: BoardTransitionsProgress::new(),
-            transition_progress: BoardTransitionsProgress::new()
+            session: BoardSession::new(),
         }
     }
 
-    fn set_transition(&mut self, transition: BoardTransition) {
-        self.transition = transition;
-        self.transition_progress = self.transition_durations.with_board_transition(&self.transition);
+    /**
+     * Draws the end of round summary over the finished game.
+     */
+    fn draw_summary(&self) {
+        let title = match self.driver.get_game_result() {
+            Some(GameResult::TimeUp) => "time up!",
+            Some(GameResult::Completed) => "complete!",
+            Some(GameResult::Failed) => "failed",
+            _ => "game over",
+        };
+
+        draw_summary(vec![
+            (title.to_string(), RED, 48.0),
+            (format!("score: {}", self.driver.get_score()), ORANGE, 32.0),
+            (format!("lines: {}", self.driver.get_lines_cleared()), YELLOW, 32.0),
+            (format!("time: {}", format_frames(self.driver.get_frames_elapsed())), GREEN, 32.0),
+            ("press enter to continue".to_string(), WHITE, 24.0),
+        ]);
     }
 }
 
@@ -156,20 +211,22 @@ impl<'a> GameState<'a> for TetrisState {
                 return;
             }
 
+            // once the game has ended, only the summary is interactive
+            if self.session.is_finished(self.driver.as_ref()) && is_key_pressed(KeyCode::Enter) {
+                return;
+            }
+
             // update the fastfall delay
             if self.fastfall_delay.0 > 0 {
                 self.fastfall_delay.0 -= 1;
             }
 
-            if self.transition.is_inert() {
-                let new_transition = self.driver.next_frame(); 
-                self.set_transition(new_transition);
-
-                if self.transition.get_points_added().is_some() {
+            if let SessionFrame::Settled { piece_placed } = self.session.update(self.driver.as_mut()) {
+                if piece_placed {
                     self.fastfall_delay.0 = self.fastfall_delay.1;
                 }
-                
-                let mut buttons = std::mem::replace(&mut self.buttons, Vec::new());
+
+                let mut buttons = std::mem::take(&mut self.buttons);
                 for button in buttons.iter_mut() {
                     button.update(self.as_mut());
                 }
@@ -178,30 +235,17 @@ impl<'a> GameState<'a> for TetrisState {
                     for button in self.buttons.iter_mut() {
                         button.reset_hold();
                     }
-    
+
                     self.reset_button_holds = false;
                 }
-            } else {
-                self.transition_progress.next_frame();
-                if self.transition_progress.is_complete() {
-                    let new_transition = self.driver.finish_transition(std::mem::replace(&mut self.transition, BoardTransition::new()));
-                    self.set_transition(new_transition);
-                }
             }
 
-            // make sure all arrays are sorted and deduped
-            self.transition.compress();
-    
-            let widget_state = WidgetState {
-                driver: self.driver.as_ref(),
-                transition: &self.transition,
-                transition_progress: self.transition_progress,
-            };
-    
-            for widget in self.widgets.iter_mut() {
-                widget.draw(widget_state, self.render_manager.get_rendering_state(widget_state));
+            self.session.draw(self.driver.as_ref(), &mut self.widgets, &mut self.render_manager);
+
+            if self.session.is_finished(self.driver.as_ref()) {
+                self.draw_summary();
             }
-    
+
             next_frame().await;
         }
     }
@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+
+use serde::{Serialize, Deserialize};
+
+use crate::debugging::drivers::recording::Action;
+
+
+const VERSUS_REPLAY_DIRECTORY: &str = "./replays/versus";
+
+/**
+ * A versus round, saved as its seed and every action each player performed.  Versus rounds are
+ * simulated the same way every time, so this is all that is needed to play one out again.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VersusReplay {
+    pub rng_seed: Vec<u8>,
+    // each action, along with the frame it was performed on and the player that performed it
+    actions: Vec<(usize, usize, Action)>,
+}
+
+impl VersusReplay {
+    pub fn new(rng_seed: Vec<u8>) -> Self {
+        Self {
+            rng_seed,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn push_actions(&mut self, frame: usize, player: usize, actions: &[Action]) {
+        self.actions.extend(actions.iter().map(|&action| (frame, player, action)));
+    }
+
+    /**
+     * The actions the player performed on the given frame.
+     */
+    pub fn get_actions(&self, frame: usize, player: usize) -> Vec<Action> {
+        let start = self.actions.partition_point(|&(f, _, _)| f < frame);
+        self.actions[start..]
+            .iter()
+            .take_while(|&&(f, _, _)| f == frame)
+            .filter(|&&(_, p, _)| p == player)
+            .map(|&(_, _, action)| action)
+            .collect()
+    }
+
+    /**
+     * Saves the replay under the name after the highest saved one, returning the name it was
+     * saved under.  Counting from the highest keeps a deleted round from being overwritten.
+     */
+    pub fn save(&self) -> io::Result<String> {
+        fs::create_dir_all(VERSUS_REPLAY_DIRECTORY)?;
+        let next_index = get_versus_replay_names()
+            .iter()
+            .filter_map(|name| get_round_index(name))
+            .max()
+            .map_or(0, |index| index + 1);
+        let name = format!("round_{}", next_index);
+        fs::write(get_filename(&name), serde_json::to_vec(self)?)?;
+
+        Ok(name)
+    }
+
+    pub fn load(name: &str) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(get_filename(name))?)?)
+    }
+}
+
+fn get_round_index(name: &str) -> Option<usize> {
+    name.strip_prefix("round_")?.parse().ok()
+}
+
+fn get_filename(name: &str) -> String {
+    format!("{}/{}.json", VERSUS_REPLAY_DIRECTORY, name)
+}
+
+/**
+ * The names of every saved round, most recent first.
+ */
+pub fn get_versus_replay_names() -> Vec<String> {
+    let mut names = fs::read_dir(VERSUS_REPLAY_DIRECTORY)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.path().file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+            .collect::<Vec<_>>())
+        .unwrap_or_default();
+    names.sort_by_key(|name| std::cmp::Reverse(get_round_index(name).unwrap_or(0)));
+
+    names
+}
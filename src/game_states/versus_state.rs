@@ -0,0 +1,517 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use super::*;
+use super::board_session::*;
+use super::menu_state::*;
+use super::versus_replay::*;
+
+use crate::battle::Battle;
+use crate::drivers::*;
+use crate::drivers::classic_driver::ClassicDriver;
+use crate::debugging::drivers::recording::Action;
+use crate::drivers::utils::tetrimino_chooser::generate_rng_seed;
+use crate::game_core::GameCore;
+use crate::game_core::tetriminos::Tetrimino;
+use crate::game_core::utils::point::Point;
+use crate::ui::game_widgets::tetris_board::TetrisBoard;
+use crate::ui::game_widgets::tetrimino_display::TetriminoDisplay;
+use crate::ui::game_widgets::garbage_meter::GarbageMeter;
+use crate::ui::game_widgets::label::Label;
+use crate::ui::game_widgets::widget::*;
+use crate::ui::button::ButtonHandler;
+use crate::ui::rendering::*;
+
+
+const HOLD_DELAY: usize = 15;
+const HOLD_RATE: usize = 3;
+const FASTFALL_HOLD: usize = 10;
+
+/**
+ * The keys each player uses to control their own board.
+ */
+pub struct KeyMap {
+    pub translate_left: KeyCode,
+    pub translate_right: KeyCode,
+    pub soft_drop: KeyCode,
+    pub hard_drop: KeyCode,
+    pub rotate_clockwise: KeyCode,
+    pub rotate_counterclockwise: KeyCode,
+    pub hold: KeyCode,
+}
+
+pub const PLAYER_ONE_KEYS: KeyMap = KeyMap {
+    translate_left: KeyCode::A,
+    translate_right: KeyCode::D,
+    soft_drop: KeyCode::S,
+    hard_drop: KeyCode::W,
+    rotate_clockwise: KeyCode::E,
+    rotate_counterclockwise: KeyCode::Q,
+    hold: KeyCode::LeftShift,
+};
+
+pub const PLAYER_TWO_KEYS: KeyMap = KeyMap {
+    translate_left: KeyCode::Left,
+    translate_right: KeyCode::Right,
+    soft_drop: KeyCode::Down,
+    hard_drop: KeyCode::Up,
+    rotate_clockwise: KeyCode::Period,
+    rotate_counterclockwise: KeyCode::Comma,
+    hold: KeyCode::Slash,
+};
+
+/**
+ * Creates the buttons that turn a player's keys into the actions they perform each frame.
+ */
+pub fn create_action_buttons(keys: &KeyMap) -> Vec<ButtonHandler<Vec<Action>, ()>> {
+    let rotate_cc = ButtonHandler::pressable(keys.rotate_counterclockwise, |actions: &mut Vec<Action>| actions.push(Action::RotateCounterClockwise));
+    let rotate_c = ButtonHandler::pressable(keys.rotate_clockwise, |actions: &mut Vec<Action>| actions.push(Action::RotateClockwise));
+    let hold = ButtonHandler::pressable(keys.hold, |actions: &mut Vec<Action>| actions.push(Action::Hold));
+
+    let left = ButtonHandler::holdable(keys.translate_left, HOLD_DELAY, HOLD_RATE, |actions: &mut Vec<Action>| actions.push(Action::TranslateLeft));
+    let right = ButtonHandler::holdable(keys.translate_right, HOLD_DELAY, HOLD_RATE, |actions: &mut Vec<Action>| actions.push(Action::TranslateRight));
+    let down = ButtonHandler::pressable(keys.soft_drop, |actions: &mut Vec<Action>| {
+        actions.push(Action::StartFastfalling);
+    }).with_release_action(|actions: &mut Vec<Action>| {
+        actions.push(Action::StopFastfalling);
+    });
+    let fastfall = ButtonHandler::pressable(keys.hard_drop, |actions: &mut Vec<Action>| actions.push(Action::Fastfall));
+
+    vec![rotate_cc, rotate_c, hold, left, right, down, fastfall]
+}
+
+/**
+ * Collects the actions performed by the buttons this frame.
+ */
+pub fn poll_action_buttons(buttons: &mut [ButtonHandler<Vec<Action>, ()>]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    for button in buttons.iter_mut() {
+        button.update(&mut actions);
+    }
+
+    actions
+}
+
+/**
+ * A copy of a player's game at some frame, along with the transition playing out on their board.
+ */
+#[derive(Clone)]
+pub struct VersusPlayerSnapshot {
+    driver: DriverSnapshot,
+    fastfall_delay: (usize, usize),
+    session: BoardSession,
+}
+
+/**
+ * One side of the split screen, with its own driver and widgets.  The player is controlled
+ * through the actions passed in each frame, so that they can come from a keyboard or over
+ * the network alike.
+ */
+pub struct VersusPlayer {
+    // the player's index in the battle
+    index: usize,
+    driver: Box<dyn Driver>,
+
+    widgets: Vec<Box<dyn Widget>>,
+    garbage_meter: GarbageMeter,
+    status_position: Point,
+    fastfall_delay: (usize, usize),
+
+    session: BoardSession,
+}
+
+impl VersusPlayer {
+    pub fn new(index: usize, mut driver: Box<dyn Driver>, area: (Point, Point)) -> Self {
+        // the battle turns every placement into attacks
+        driver.get_driver_core_mut().collect_lock_results();
+
+        let area_dimensions = area.1 - area.0;
+        let board_height = area_dimensions.y() as f32 * 0.8;
+        let board_dimensions = Point((board_height * 0.5) as i32, 10 + board_height as i32);
+        let board_position = area.0 + Point((area_dimensions.x() - board_dimensions.x()) / 2, (area_dimensions.y() - board_dimensions.y()) / 2);
+        let tetris_board = TetrisBoard::new((board_position, board_position + board_dimensions));
+
+        // the garbage meter sits right up against the left side of the board
+        let meter_position = board_position - Point::unit_x(14);
+        let garbage_meter = GarbageMeter::new((meter_position, meter_position + Point(10, board_dimensions.y())));
+
+        let tetrimino_display_dimensions = Point(60, 60);
+        let hold_position = meter_position - Point::unit_x(tetrimino_display_dimensions.x() + 10) + Point(0, 20);
+        let hold_display = TetriminoDisplay::new(
+                (hold_position, hold_position + tetrimino_display_dimensions),
+                driver.get_game_core(),
+                |core| core.get_held());
+
+        let function_points: Vec<for <'b> fn(&'b GameCore) -> Option<&'b Tetrimino>> = vec![
+            |core| Some(core.get_next_tetrimino(0)),
+            |core| Some(core.get_next_tetrimino(1)),
+            |core| Some(core.get_next_tetrimino(2)),
+        ];
+        let queue_display = (0..driver.get_game_core().get_tetrimino_queue_length())
+            .map(|i| {
+                let position = board_position + Point(board_dimensions.x() + 10, 20 + 80 * i as i32);
+                TetriminoDisplay::new(
+                    (position, position + tetrimino_display_dimensions),
+                    driver.get_game_core(),
+                    function_points[i])
+            })
+            .collect::<Vec<_>>();
+
+        let lines_position = board_position + Point(0, board_dimensions.y() + 30);
+        let lines_display = Label::new(
+            lines_position,
+            YELLOW,
+            28.0,
+            |driver| format!("lines: {}", driver.get_lines_cleared()));
+
+        let status_position = board_position + Point(board_dimensions.x() + 10, 280);
+
+        let mut widgets: Vec<Box<dyn Widget>> = Vec::new();
+        widgets.push(Box::new(tetris_board));
+        widgets.push(Box::new(hold_display));
+        for d in queue_display.into_iter() {
+            widgets.push(Box::new(d));
+        }
+        widgets.push(Box::new(lines_display));
+
+        Self {
+            index,
+            driver,
+
+            widgets,
+            garbage_meter,
+            status_position,
+            fastfall_delay: (0, FASTFALL_HOLD),
+
+            session: BoardSession::new(),
+        }
+    }
+
+    pub fn get_driver(&self) -> &dyn Driver {
+        self.driver.as_ref()
+    }
+
+    pub fn take_snapshot(&self) -> VersusPlayerSnapshot {
+        VersusPlayerSnapshot {
+            driver: self.driver.take_snapshot(),
+            fastfall_delay: self.fastfall_delay,
+            session: self.session.clone(),
+        }
+    }
+
+    pub fn restore_snapshot(&mut self, snapshot: &VersusPlayerSnapshot) {
+        self.driver.restore_snapshot(&snapshot.driver);
+        self.fastfall_delay = snapshot.fastfall_delay;
+        self.session = snapshot.session.clone();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.session.is_finished(self.driver.as_ref())
+    }
+
+    /**
+     * Actions are only performed while the board is settled, and are dropped otherwise.
+     */
+    pub fn is_inert(&self) -> bool {
+        self.session.is_inert()
+    }
+
+    /**
+     * Simulates a single frame of this player's game, the same way a single player game is,
+     * except that the battle gets to see every frame and transition.
+     */
+    pub fn update(&mut self, battle: &mut Battle, actions: &[Action]) {
+        if self.fastfall_delay.0 > 0 {
+            self.fastfall_delay.0 -= 1;
+        }
+
+        let index = self.index;
+        let frame = self.session.update_with(
+            self.driver.as_mut(),
+            battle,
+            |battle, driver| battle.next_frame(index, driver),
+            |battle, driver, transition| battle.finish_transition(index, driver, transition));
+
+        if let SessionFrame::Settled { piece_placed } = frame {
+            if piece_placed {
+                self.fastfall_delay.0 = self.fastfall_delay.1;
+            }
+
+            for &action in actions.iter() {
+                if let Action::Fastfall = action {
+                    if self.fastfall_delay.0 > 0 {
+                        continue
+                    }
+                }
+
+                let new_transition = action.apply(self.driver.as_mut());
+                if !new_transition.is_inert() {
+                    self.session.set_transition(new_transition);
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, render_manager: &mut RenderManager, battle: &Battle) {
+        self.session.draw(self.driver.as_ref(), &mut self.widgets, render_manager);
+
+        let widget_state = self.session.get_widget_state(self.driver.as_ref());
+        self.garbage_meter.set_garbage_pending(battle.get_garbage_pending(self.index));
+        self.garbage_meter.draw(widget_state, render_manager.get_rendering_state(widget_state));
+
+        for (i, line) in battle.get_status(self.index).iter().enumerate() {
+            let y = self.status_position.y() as f32 + i as f32 * 24.0;
+            draw_text(line, self.status_position.x() as f32, y, 24.0, SKYBLUE);
+        }
+    }
+
+    pub fn draw_name(&self, name: &str) {
+        draw_text(name, self.status_position.x() as f32, self.status_position.y() as f32 - 32.0, 28.0, WHITE);
+    }
+}
+
+/**
+ * Once only one player is left standing, they have won, and the match is over.
+ */
+pub fn end_match_if_decided(battle: &Battle, players: &mut [VersusPlayer]) {
+    if battle.is_decided() {
+        for player in players.iter_mut() {
+            player.driver.get_driver_core_mut().end_game(GameResult::Completed);
+        }
+    }
+}
+
+/**
+ * The only player that won the match, if there is one.
+ */
+pub fn get_match_winner(players: &[VersusPlayer]) -> Option<usize> {
+    let winners = players
+        .iter()
+        .enumerate()
+        .filter(|(_, player)| player.get_driver().get_game_result() == Some(GameResult::Completed))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    match winners.as_slice() {
+        [winner] => Some(*winner),
+        _ => None,
+    }
+}
+
+/**
+ * Describes the result of the match, once every board has settled.
+ */
+pub fn get_match_result(players: &[VersusPlayer]) -> String {
+    match get_match_winner(players) {
+        Some(winner) => format!("player {} wins!", winner + 1),
+        None => "draw".to_string(),
+    }
+}
+
+/**
+ * Draws a message over the middle of the screen, which is dismissed with enter.
+ */
+pub fn draw_message_box(title: &str, color: Color) {
+    let dimensions = Point(400, 160);
+    let position = Point((screen_width() as i32 - dimensions.x()) / 2, (screen_height() as i32 - dimensions.y()) / 2);
+    draw_rectangle(
+        position.x() as f32,
+        position.y() as f32,
+        dimensions.x() as f32,
+        dimensions.y() as f32,
+        Color::new(0.0, 0.0, 0.0, 0.85),
+    );
+
+    let lines = vec![
+        (title, color, 48.0),
+        ("press enter to continue", WHITE, 24.0),
+    ];
+
+    for (i, (text, color, font_size)) in lines.into_iter().enumerate() {
+        let dim = measure_text(text, None, font_size as u16, 1.0);
+        let x_pos = position.x() as f32 + (dimensions.x() as f32 - dim.width) / 2.0;
+        let y_pos = position.y() as f32 + 60.0 + 55.0 * i as f32;
+        draw_text(text, x_pos, y_pos, font_size, color);
+    }
+}
+
+/**
+ * How a versus round ended, along with everything needed to replay it.
+ */
+pub struct RoundResult {
+    // the index of the winning player, unless the round was a draw
+    pub winner: Option<usize>,
+    // the name the round's replay was saved under
+    pub replay: Result<String, String>,
+}
+
+/**
+ * Two players on one screen, each clearing lines to send garbage beneath the other's stack.
+ * The last player left standing wins.
+ */
+pub struct VersusState {
+    battle: Battle,
+    players: Vec<VersusPlayer>,
+    player_names: Vec<String>,
+    buttons: Vec<Vec<ButtonHandler<Vec<Action>, ()>>>,
+    render_manager: RenderManager,
+
+    current_frame: usize,
+    recording: VersusReplay,
+    // the round being replayed, whose actions are used instead of the keys
+    playback: Option<VersusReplay>,
+    // where the result goes once the round is over, for whatever started it
+    round_result: Option<Rc<RefCell<Option<RoundResult>>>>,
+    // every round played is saved as soon as it's over
+    saved_replay: Option<Result<String, String>>,
+}
+
+impl VersusState {
+    /**
+     * Both players are dealt the same tetriminos, from the given seed.
+     */
+    pub fn new(rng_seed: Vec<u8>, render_manager: RenderManager) -> Self {
+        let keys = [PLAYER_ONE_KEYS, PLAYER_TWO_KEYS];
+        let area_width = screen_width() as i32 / keys.len() as i32;
+        let players = (0..keys.len())
+            .map(|i| {
+                let driver = DriverBuilder::<ClassicDriver>::new().with_rng_seed(rng_seed.clone()).build_boxed();
+                let area = (Point(area_width * i as i32, 0), Point(area_width * (i as i32 + 1), screen_height() as i32));
+                VersusPlayer::new(i, driver, area)
+            })
+            .collect();
+        let buttons = keys.iter().map(create_action_buttons).collect();
+
+        Self {
+            battle: Battle::new(keys.len(), &rng_seed),
+            players,
+            player_names: (0..keys.len()).map(|i| format!("player {}", i + 1)).collect(),
+            buttons,
+            render_manager,
+
+            current_frame: 0,
+            recording: VersusReplay::new(rng_seed),
+            playback: None,
+            round_result: None,
+            saved_replay: None,
+        }
+    }
+
+    pub fn with_player_names(mut self, player_names: Vec<String>) -> Self {
+        self.player_names = player_names;
+        self
+    }
+
+    pub fn with_playback(mut self, playback: VersusReplay) -> Self {
+        self.playback = Some(playback);
+        self
+    }
+
+    pub fn with_round_result(mut self, round_result: Rc<RefCell<Option<RoundResult>>>) -> Self {
+        self.round_result = Some(round_result);
+        self
+    }
+
+    fn get_result_message(&self) -> String {
+        match get_match_winner(&self.players) {
+            Some(winner) => format!("{} wins!", self.player_names[winner]),
+            None => "draw".to_string(),
+        }
+    }
+}
+
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for VersusState {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::P) {
+                gamestate_manager.get_gamestate_stack().push(self);
+                gamestate_manager.get_gamestate_stack().push(MenuState::new(Vec::new()).await.boxed());
+                return;
+            }
+
+            let finished = self.players.iter().all(|player| player.is_finished());
+            if finished && self.playback.is_none() && self.saved_replay.is_none() {
+                self.saved_replay = Some(self.recording.save().map_err(|e| e.to_string()));
+            }
+
+            if finished && is_key_pressed(KeyCode::Enter) {
+                if let (Some(round_result), Some(replay)) = (self.round_result.as_ref(), self.saved_replay.clone()) {
+                    *round_result.borrow_mut() = Some(RoundResult {
+                        winner: get_match_winner(&self.players),
+                        replay,
+                    });
+                }
+                return;
+            }
+
+            for (i, (player, buttons)) in self.players.iter_mut().zip(self.buttons.iter_mut()).enumerate() {
+                // keys are only read while they can be acted upon, the same as in a single player game
+                let actions = match self.playback.as_ref() {
+                    Some(playback) => playback.get_actions(self.current_frame, i),
+                    None if player.is_inert() => poll_action_buttons(buttons),
+                    None => Vec::new(),
+                };
+
+                self.recording.push_actions(self.current_frame, i, &actions);
+                player.update(&mut self.battle, &actions);
+            }
+            end_match_if_decided(&self.battle, &mut self.players);
+            self.current_frame += 1;
+
+            for (player, name) in self.players.iter_mut().zip(self.player_names.iter()) {
+                player.draw(&mut self.render_manager, &self.battle);
+                player.draw_name(name);
+            }
+
+            if finished {
+                draw_message_box(&self.get_result_message(), RED);
+            }
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Starts a single versus match.
+ */
+pub async fn construct_versus_gamestate<'a>(factory: &mut GameStateManager<'a>) -> Box<dyn GameState<'a> + 'a> {
+    VersusState::new(
+        generate_rng_seed(),
+        factory.get_render_manager_factory().start_building().build().await
+    ).boxed()
+}
+
+async fn construct_versus_replay_gamestate<'a>(factory: &mut GameStateManager<'a>, name: String) -> Box<dyn GameState<'a> + 'a> {
+    let replay = match VersusReplay::load(&name) {
+        Ok(replay) => replay,
+        Err(_) => return MenuState::new(Vec::new()).await.boxed(),
+    };
+
+    VersusState::new(
+        replay.rng_seed.clone(),
+        factory.get_render_manager_factory().start_building().build().await
+    ).with_playback(replay).boxed()
+}
+
+/**
+ * Lists every saved versus round, any of which can be watched again.
+ */
+pub async fn get_versus_replay_menu<'a>() -> Box<dyn GameState<'a> + 'a> {
+    let menu_options = get_versus_replay_names()
+        .into_iter()
+        .map(|name| MenuOption::new(
+            name.clone(),
+            move |f| Box::pin(construct_versus_replay_gamestate(f, name.clone()))
+        ))
+        .collect::<Vec<_>>();
+
+    Box::new(MenuState::new(menu_options).await)
+}
@@ -9,32 +9,47 @@ mod drivers;
 mod game_states;
 mod ui;
 mod debugging;
+mod battle;
+mod netplay;
+mod spectating;
+mod match_server;
 
 use drivers::*;
+use drivers::utils::tetrimino_chooser::generate_rng_seed;
 
 use game_states::*;
 use game_states::menu_state::*;
 use game_states::tetris_state::*;
 
 use classic_driver::ClassicDriver;
-use cascade_driver::CascadeDriver;
+use cascade_driver::{CascadeDriver, CascadeGenerator};
 use sticky_driver::StickyDriver;
-use fusion_driver::FusionDriver;
+use fusion_driver::{FusionDriver, FusionLevel};
+use ultra_driver::UltraDriver;
+use dig_driver::DigDriver;
+use survival_driver::SurvivalDriver;
+use master_driver::MasterDriver;
+use color_match_driver::ColorMatchDriver;
+use item_driver::ItemDriver;
+use perfect_clear_driver::PerfectClearDriver;
+use zone_driver::ZoneDriver;
 use debugging::drivers::recording::RecordingDriver;
 use debugging::drivers::replaying::ReplayingDriver;
 use debugging::recording_manager::for_recording_if_enabled;
+use spectating::broadcasting_driver::BroadcastingDriver;
 
 use ui::rendering::*;
 
 
 pub struct GameMode {
     pub name: &'static str,
-    pub get_driver: fn() -> Box<dyn Driver>,
+    // builds the mode's driver from the given seed
+    pub get_driver: fn(Vec<u8>) -> Box<dyn Driver>,
     pub get_renderer: fn(&mut RenderManagerFactory) -> RenderManagerBuilder,
 }
 
 impl GameMode {
-    fn new(name: &'static str, get_driver: fn() -> Box<dyn Driver>) -> Self {
+    fn new(name: &'static str, get_driver: fn(Vec<u8>) -> Box<dyn Driver>) -> Self {
         Self {
             name, get_driver, 
             get_renderer: |x| x.start_building(),
@@ -46,8 +61,8 @@ impl GameMode {
         self
     }
 
-    async fn construct_gamestate<'a>(&self, factory: &mut GameStateManager<'a>) -> Box<dyn GameState<'a> + 'a> {
-        let mut driver = (self.get_driver)();
+    async fn construct_gamestate<'a>(&self, factory: &mut GameStateManager<'a>, broadcast: bool) -> Box<dyn GameState<'a> + 'a> {
+        let mut driver = (self.get_driver)(generate_rng_seed());
 
         /*
          * If the debug option is included in compilation, any game will be recorded in case
@@ -60,6 +75,10 @@ impl GameMode {
             }
         }
 
+        if broadcast {
+            driver = Box::new(BroadcastingDriver::new(driver, self.name));
+        }
+
         TetrisState::new(
             driver,
             (self.get_renderer)(factory.get_render_manager_factory()).build().await
@@ -67,16 +86,84 @@ impl GameMode {
     }
 }
 
-#[macroquad::main("TetRust")]
-async fn main() {
+/**
+ * Runs the game, unless asked to run a match server or bots on the command line instead.  Both
+ * of those run without a window.
+ */
+fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    let count_arg = |default| args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(default);
+
+    match args.get(1).map(String::as_str) {
+        Some("--server") => {
+            if let Err(e) = match_server::server::run_server(count_arg(match_server::MAX_PLAYERS)) {
+                eprintln!("could not run the match server: {}", e);
+            }
+        },
+        Some("--bots") => match_server::bot::run_bots("localhost", count_arg(match_server::MAX_PLAYERS - 1)),
+        _ => macroquad::Window::new("TetRust", run_game()),
+    }
+}
+
+async fn run_game() {
     let gamemodes = vec![
-        GameMode::new("classic", || DriverBuilder::<ClassicDriver>::new().configured(for_recording_if_enabled).build_boxed())
+        GameMode::new("classic", |rng_seed| DriverBuilder::<ClassicDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed())
             .with_get_renderer(|f| f.start_building()
                 .with_tilemap("res/basic_tilemap.png", "res/basic_tilemap_info.json")),
-        GameMode::new("cascade", || DriverBuilder::<CascadeDriver>::new().configured(for_recording_if_enabled).build_boxed())
+        GameMode::new("cascade", |rng_seed| DriverBuilder::<CascadeDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed())
             .with_get_renderer(|f| f.start_building()),
-        GameMode::new("sticky", || DriverBuilder::<StickyDriver>::new().configured(for_recording_if_enabled).build_boxed()),
-        GameMode::new("fusion", || DriverBuilder::<FusionDriver>::new().configured(for_recording_if_enabled).build_boxed())
+        GameMode::new("sticky", |rng_seed| DriverBuilder::<StickyDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("fusion", |rng_seed| DriverBuilder::<FusionDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_levels(FusionLevel::load_levels("res/fusion_levels.json"))
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("ultra", |rng_seed| DriverBuilder::<UltraDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("ultra short", |rng_seed| DriverBuilder::<UltraDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_time_limit(game_core::defaults::settings::SHORT_ULTRA_TIME_LIMIT)
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("dig", |rng_seed| DriverBuilder::<DigDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("survival", |rng_seed| DriverBuilder::<SurvivalDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("master", |rng_seed| DriverBuilder::<MasterDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("fading", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_stack_visibility(StackVisibility::fading(
+                game_core::defaults::settings::FADING_STACK_FRAMES,
+                game_core::defaults::settings::LINE_CLEAR_FLASH_FRAMES))
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("invisible", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_stack_visibility(StackVisibility::invisible(game_core::defaults::settings::LINE_CLEAR_FLASH_FRAMES))
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("big", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_big_tetriminos(false)
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("big half step", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_big_tetriminos(true)
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("color match", |rng_seed| DriverBuilder::<ColorMatchDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("items", |rng_seed| DriverBuilder::<ItemDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("zone", |rng_seed| DriverBuilder::<ZoneDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("perfect clear", |rng_seed| DriverBuilder::<PerfectClearDriver>::new().with_rng_seed(rng_seed).configured(for_recording_if_enabled).build_boxed()),
+        GameMode::new("classic cascade", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_tetrimino_generator(CascadeGenerator::new(game_core::defaults::tetriminos::TETRIMINOS))
+            .with_clear_physics(CascadePhysics::new())
+            .configured(for_recording_if_enabled)
+            .build_boxed()),
+        GameMode::new("classic full gravity", |rng_seed| DriverBuilder::<ClassicDriver>::new()
+            .with_rng_seed(rng_seed)
+            .with_clear_physics(FullGravityPhysics::new())
+            .configured(for_recording_if_enabled)
+            .build_boxed())
     ];
 
     let gamemode_names = gamemodes.iter().map(|gamemode| gamemode.name).collect::<Vec<_>>();
@@ -90,11 +177,46 @@ async fn main() {
                 .into_iter()
                 .map(|name| {
                     MenuOption::new(name.to_string(), move |f| {
-                        Box::pin(gamemodes_ref[name].construct_gamestate(f))
+                        Box::pin(gamemodes_ref[name].construct_gamestate(f, false))
                     })
                 })
                 .collect::<Vec<_>>();
 
+    menu_options.push(
+        MenuOption::new("versus".to_string(), |_| {
+            Box::pin(game_states::set_state::get_set_menu())
+        }));
+
+    menu_options.push(
+        MenuOption::new("online versus".to_string(), |_| {
+            Box::pin(game_states::netplay_state::get_netplay_menu())
+        }));
+
+    menu_options.push(
+        MenuOption::new("co-op".to_string(), |f| {
+            Box::pin(game_states::coop_state::construct_coop_gamestate(f))
+        }));
+
+    menu_options.push(
+        MenuOption::new("broadcast".to_string(), move |_| {
+            Box::pin(spectating::spectator_menu::get_broadcast_menu(gamemodes_ref))
+        }));
+
+    menu_options.push(
+        MenuOption::new("spectate".to_string(), move |_| {
+            Box::pin(spectating::spectator_menu::get_spectator_state(gamemodes_ref))
+        }));
+
+    menu_options.push(
+        MenuOption::new("battle royale".to_string(), move |_| {
+            Box::pin(match_server::match_menu::get_match_menu(gamemodes_ref))
+        }));
+
+    menu_options.push(
+        MenuOption::new("puzzles".to_string(), |_| {
+            Box::pin(game_states::puzzle_menu::get_puzzle_pack_menu())
+        }));
+
     if cfg!(feature = "debug") {
         menu_options.push(
             MenuOption::new("replay".to_string(), move |_| {
@@ -0,0 +1,94 @@
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::battle::targeting::TargetingStrategy;
+use crate::drivers::*;
+use crate::drivers::classic_driver::ClassicDriver;
+use crate::drivers::utils::tetrimino_chooser::generate_rng_seed;
+use crate::game_core::defaults::settings::FRAMES_PER_SECOND;
+use crate::netplay::connection::Connection;
+use super::*;
+use super::match_client_driver::MatchClientDriver;
+
+
+// bots place a piece this often, which is slow enough for a match to last a while
+const FRAMES_PER_PLACEMENT: usize = FRAMES_PER_SECOND / 2;
+// a bot gives up on a match that hasn't started after this long, such as when it's already full
+const JOIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/**
+ * Plays a single game in a match without a screen, moving each piece to a random column and
+ * rotation before dropping it.  Transitions are finished straight away, since nobody is
+ * watching them.
+ */
+pub fn run_bot(host: &str, strategy: TargetingStrategy) -> io::Result<()> {
+    let connection = Connection::connect(host, MATCH_PORT)?;
+    let wrapped = DriverBuilder::<ClassicDriver>::new().with_rng_seed(generate_rng_seed()).build_boxed();
+    let mut driver = MatchClientDriver::new(wrapped, connection, strategy);
+    let mut rng = rand::thread_rng();
+
+    let frame_duration = Duration::from_secs(1) / FRAMES_PER_SECOND as u32;
+    let joined_at = Instant::now();
+    let mut next_frame_at = joined_at;
+    let mut frames_until_placement = FRAMES_PER_PLACEMENT;
+    while !driver.is_game_over() {
+        let mut transition = driver.next_frame();
+        if !driver.is_started() && joined_at.elapsed() > JOIN_TIMEOUT {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "the match never started"));
+        }
+
+        if driver.is_started() && transition.is_inert() {
+            if frames_until_placement == 0 {
+                for _ in 0..rng.gen_range(0..4) {
+                    driver.rotate_clockwise();
+                }
+                let translation: i32 = rng.gen_range(-5..5);
+                for _ in 0..translation.abs() {
+                    if translation < 0 {
+                        driver.translate_left();
+                    } else {
+                        driver.translate_right();
+                    }
+                }
+
+                transition = driver.fastfall();
+                frames_until_placement = FRAMES_PER_PLACEMENT;
+            } else {
+                frames_until_placement -= 1;
+            }
+        }
+
+        while !transition.is_inert() {
+            transition = driver.finish_transition(transition);
+        }
+
+        next_frame_at += frame_duration;
+        thread::sleep(next_frame_at.saturating_duration_since(Instant::now()));
+    }
+
+    Ok(())
+}
+
+/**
+ * Fills a match with bots, each on its own thread with a random targeting strategy, and
+ * waits for all of their games to end.
+ */
+pub fn run_bots(host: &str, count: usize) {
+    let bots = (0..count)
+        .map(|_| {
+            let host = host.to_string();
+            let strategy = *TargetingStrategy::ALL.choose(&mut rand::thread_rng()).unwrap();
+            thread::spawn(move || run_bot(&host, strategy))
+        })
+        .collect::<Vec<_>>();
+
+    for (i, bot) in bots.into_iter().enumerate() {
+        if let Ok(Err(e)) = bot.join() {
+            eprintln!("bot {} could not play: {}", i + 1, e);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+
+use crate::battle::raise_garbage;
+use crate::battle::targeting::*;
+use crate::drivers::*;
+use crate::drivers::utils::tetrimino_chooser::{rng_from_seed, SeededRng};
+use crate::game_core::utils::point::Point;
+use crate::netplay::connection::Connection;
+use super::protocol::*;
+
+
+/**
+ * Plays the wrapped game as one player in a match on the match server.  Nothing moves until
+ * every player has joined, then each placement is reported to the server and any garbage the
+ * server sends back rises beneath the stack.
+ */
+pub struct MatchClientDriver<'a> {
+    wrapped: Box<dyn Driver + 'a>,
+    connection: Connection,
+    strategy: TargetingStrategy,
+    // chooses the holes in the garbage this player receives
+    rng: SeededRng,
+
+    joined: usize,
+    num_players: usize,
+    started: bool,
+    // the lines from each attack the server has sent, which rise on the next frame
+    garbage: Vec<usize>,
+    standing: usize,
+    kos: usize,
+    badge_points: usize,
+    attackers: usize,
+    garbage_pending: usize,
+    place: Option<usize>,
+    reported_game_over: bool,
+    // without the server, nobody is left to say where the player placed
+    disconnected: bool,
+}
+
+impl<'a> MatchClientDriver<'a> {
+    pub fn new(mut wrapped: Box<dyn Driver + 'a>, mut connection: Connection, strategy: TargetingStrategy) -> Self {
+        wrapped.get_driver_core_mut().collect_lock_results();
+        let rng = rng_from_seed(wrapped.get_driver_core().get_rng_seed());
+        let _ = connection.send(&ClientMessage::Join { strategy });
+
+        Self {
+            wrapped,
+            connection,
+            strategy,
+            rng,
+
+            joined: 0,
+            num_players: 0,
+            started: false,
+            garbage: Vec::new(),
+            standing: 0,
+            kos: 0,
+            badge_points: 0,
+            attackers: 0,
+            garbage_pending: 0,
+            place: None,
+            reported_game_over: false,
+            disconnected: false,
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /**
+     * The player can only move while the match is on and their own game isn't over.
+     */
+    fn is_playing(&self) -> bool {
+        self.started && !self.wrapped.is_game_over()
+    }
+
+    fn receive_messages(&mut self) {
+        loop {
+            match self.connection.receive() {
+                Ok(Some(ServerMessage::Lobby { joined, num_players })) => {
+                    self.joined = joined;
+                    self.num_players = num_players;
+                },
+                Ok(Some(ServerMessage::Start)) => {
+                    self.started = true;
+                    self.standing = self.num_players;
+                },
+                Ok(Some(ServerMessage::Garbage { lines })) => self.garbage.extend(lines),
+                Ok(Some(ServerMessage::Status { standing, kos, badge_points, attackers, garbage_pending })) => {
+                    self.standing = standing;
+                    self.kos = kos;
+                    self.badge_points = badge_points;
+                    self.attackers = attackers;
+                    self.garbage_pending = garbage_pending;
+                },
+                Ok(Some(ServerMessage::KnockedOut { place })) => {
+                    self.place = Some(place);
+                    self.end_game(GameResult::ToppedOut);
+                },
+                Ok(Some(ServerMessage::Won)) => {
+                    self.place = Some(1);
+                    self.end_game(GameResult::Completed);
+                },
+                Ok(None) => break,
+                // the match can't go on without the server
+                Err(_) => {
+                    self.disconnected = true;
+                    self.end_game(GameResult::ToppedOut);
+                    break;
+                },
+            }
+        }
+    }
+
+    fn end_game(&mut self, result: GameResult) {
+        // the server already knows the game is over, whoever ended it
+        self.reported_game_over = true;
+        if !self.wrapped.is_game_over() {
+            self.wrapped.get_driver_core_mut().end_game(result);
+        }
+    }
+
+    fn report_game_over(&mut self) {
+        if self.wrapped.is_game_over() && !self.reported_game_over {
+            self.reported_game_over = true;
+            let _ = self.connection.send(&ClientMessage::ToppedOut);
+        }
+    }
+
+}
+
+impl<'a> Driver for MatchClientDriver<'a> {
+    fn get_driver_core(&self) -> &DriverCore {
+        self.wrapped.get_driver_core()
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        self.wrapped.get_driver_core_mut()
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        self.wrapped.get_mode_state()
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        self.wrapped.set_mode_state(mode_state)
+    }
+
+    fn take_snapshot(&self) -> DriverSnapshot {
+        self.wrapped.take_snapshot()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        self.wrapped.restore_snapshot(snapshot)
+    }
+
+    /**
+     * A player's game is only over once the server has told them where they placed, so that
+     * frames keep coming, and messages keep being received, until it has.
+     */
+    fn is_game_over(&self) -> bool {
+        self.wrapped.is_game_over() && (self.place.is_some() || self.disconnected)
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        if !self.started {
+            return vec![format!("waiting for players: {}/{}", self.joined, self.num_players)]
+        }
+
+        let mut status = self.wrapped.get_mode_status();
+        match self.place {
+            Some(place) => status.push(format!("place: {}/{}", place, self.num_players)),
+            None => status.push(format!("players left: {}", self.standing)),
+        }
+        status.push(format!("KOs: {}", self.kos));
+        status.push(format!("badges: {}", get_badge_level(self.badge_points)));
+        status.push(format!("targeting: {}", self.strategy.get_name()));
+        if self.attackers > 0 {
+            status.push(format!("attackers: {}", self.attackers));
+        }
+        if self.garbage_pending > 0 {
+            status.push(format!("garbage: {}", self.garbage_pending));
+        }
+
+        status
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        self.wrapped.get_target_points()
+    }
+
+    /**
+     * Garbage rises while the board is settled, in place of the next frame.
+     */
+    fn next_frame(&mut self) -> BoardTransition {
+        self.receive_messages();
+        if !self.started || self.wrapped.is_game_over() {
+            self.report_game_over();
+            return BoardTransition::new()
+        }
+
+        let transition = if self.garbage.is_empty() {
+            self.wrapped.next_frame()
+        } else {
+            raise_garbage(self.wrapped.as_mut(), std::mem::take(&mut self.garbage), &mut self.rng)
+        };
+
+        self.report_game_over();
+        transition
+    }
+
+    // nothing moves until the match starts, or after the player's game is over
+    fn translate_left(&mut self) {
+        if self.is_playing() {
+            self.wrapped.translate_left()
+        }
+    }
+
+    fn translate_right(&mut self) {
+        if self.is_playing() {
+            self.wrapped.translate_right()
+        }
+    }
+
+    fn rotate_clockwise(&mut self) {
+        if self.is_playing() {
+            self.wrapped.rotate_clockwise()
+        }
+    }
+
+    fn rotate_counterclockwise(&mut self) {
+        if self.is_playing() {
+            self.wrapped.rotate_counterclockwise()
+        }
+    }
+
+    fn hold(&mut self) {
+        if self.is_playing() {
+            self.wrapped.hold()
+        }
+    }
+
+    fn activate_zone(&mut self) {
+        if self.is_playing() {
+            self.wrapped.activate_zone()
+        }
+    }
+
+    fn start_fastfalling(&mut self) {
+        if self.is_playing() {
+            self.wrapped.start_fastfalling()
+        }
+    }
+
+    fn stop_fastfalling(&mut self) {
+        if self.is_playing() {
+            self.wrapped.stop_fastfalling()
+        }
+    }
+
+    fn fall(&mut self) -> BoardTransition {
+        if !self.is_playing() {
+            return BoardTransition::new()
+        }
+
+        self.wrapped.fall()
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        if !self.is_playing() {
+            return BoardTransition::new()
+        }
+
+        self.wrapped.fastfall()
+    }
+
+    /**
+     * Reports each placement to the server, once its transition is over.
+     */
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
+        let transition = self.wrapped.finish_transition(transition);
+
+        let stack_height = self.wrapped.get_game_core().get_board().num_active_rows();
+        for lock_result in self.wrapped.get_driver_core_mut().take_lock_results() {
+            if self.connection.send(&ClientMessage::Placement { lock_result, stack_height }).is_err() {
+                self.end_game(GameResult::ToppedOut);
+            }
+        }
+
+        self.report_game_over();
+        transition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+    use crate::drivers::classic_driver::ClassicDriver;
+    use crate::netplay::connection::Host;
+    use crate::match_server::MATCH_PORT;
+
+    fn receive_from_client(server: &mut Connection) -> ClientMessage {
+        loop {
+            if let Some(message) = server.receive().unwrap() {
+                return message
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn a_knocked_out_player_waits_to_hear_their_place() {
+        let host = Host::new(MATCH_PORT + 10).unwrap();
+        let wrapped = DriverBuilder::<ClassicDriver>::new().with_rng_seed(vec![1]).build_boxed();
+        let connection = Connection::connect("127.0.0.1", MATCH_PORT + 10).unwrap();
+        let mut driver = MatchClientDriver::new(wrapped, connection, TargetingStrategy::ALL[0]);
+        let mut server = loop {
+            if let Some(server) = host.accept().unwrap() {
+                break server
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        assert!(matches!(receive_from_client(&mut server), ClientMessage::Join { .. }));
+        server.send(&ServerMessage::Lobby { joined: 2, num_players: 2 }).unwrap();
+        server.send(&ServerMessage::Start).unwrap();
+        while !driver.is_started() {
+            driver.next_frame();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // the player tops out, but is still playing until the server says where they placed
+        driver.get_driver_core_mut().end_game(GameResult::ToppedOut);
+        driver.next_frame();
+        assert!(!driver.is_game_over());
+        assert!(matches!(receive_from_client(&mut server), ClientMessage::ToppedOut));
+
+        server.send(&ServerMessage::KnockedOut { place: 2 }).unwrap();
+        while !driver.is_game_over() {
+            driver.next_frame();
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(driver.get_mode_status().contains(&"place: 2/2".to_string()));
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use crate::GameMode;
+use crate::battle::targeting::TargetingStrategy;
+use crate::drivers::utils::tetrimino_chooser::generate_rng_seed;
+use crate::game_states::*;
+use crate::game_states::menu_state::*;
+use crate::game_states::tetris_state::TetrisState;
+use crate::netplay::connection::Connection;
+use super::*;
+use super::match_client_driver::MatchClientDriver;
+
+
+const SERVER_HOST: &str = "localhost";
+// matches on the server are always played in this mode
+const MATCH_MODE: &str = "classic";
+
+/**
+ * Keeps trying to connect to the match server, then joins its match with the chosen strategy.
+ */
+pub struct MatchConnectingState<'a> {
+    gamemodes: &'a HashMap<&'static str, GameMode>,
+    strategy: TargetingStrategy,
+    frames_until_retry: usize,
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for MatchConnectingState<'a> {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            if self.frames_until_retry == 0 {
+                if let Ok(connection) = Connection::connect(SERVER_HOST, MATCH_PORT) {
+                    let gamemode = &self.gamemodes[MATCH_MODE];
+                    let driver = (gamemode.get_driver)(generate_rng_seed());
+                    let state = TetrisState::new(
+                        Box::new(MatchClientDriver::new(driver, connection, self.strategy)),
+                        (gamemode.get_renderer)(gamestate_manager.get_render_manager_factory()).build().await
+                    );
+                    gamestate_manager.get_gamestate_stack().push(state.boxed());
+                    return;
+                }
+
+                self.frames_until_retry = 60;
+            } else {
+                self.frames_until_retry -= 1;
+            }
+
+            let lines = [
+                (format!("looking for a match server on {}:{}", SERVER_HOST, MATCH_PORT), WHITE, 32.0, 0.0),
+                ("escape to cancel".to_string(), GRAY, 24.0, 40.0),
+            ];
+            for (text, color, font_size, y_offset) in lines.iter() {
+                let dim = measure_text(text, None, *font_size as u16, 1.0);
+                draw_text(text, (screen_width() - dim.width) / 2.0, screen_height() / 2.0 + y_offset, *font_size, *color);
+            }
+
+            next_frame().await;
+        }
+    }
+}
+
+/**
+ * Lists each targeting strategy, any of which joins the match on the match server.
+ */
+pub async fn get_match_menu<'a>(gamemodes: &'a HashMap<&'static str, GameMode>) -> Box<dyn GameState<'a> + 'a> {
+    let menu_options = TargetingStrategy::ALL
+        .iter()
+        .map(|&strategy| MenuOption::new(
+            format!("target {}", strategy.get_name()),
+            move |_| Box::pin(async move {
+                MatchConnectingState {
+                    gamemodes,
+                    strategy,
+                    frames_until_retry: 0,
+                }.boxed()
+            })
+        ))
+        .collect::<Vec<_>>();
+
+    Box::new(MenuState::new(menu_options).await)
+}
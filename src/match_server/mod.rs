@@ -0,0 +1,12 @@
+pub mod bot;
+pub mod match_menu;
+pub mod match_client_driver;
+pub mod protocol;
+pub mod server;
+
+use crate::netplay::connection::NETPLAY_PORT;
+
+
+pub const MATCH_PORT: u16 = NETPLAY_PORT + 3;
+pub const MIN_PLAYERS: usize = 3;
+pub const MAX_PLAYERS: usize = 99;
@@ -0,0 +1,39 @@
+use serde::{Serialize, Deserialize};
+
+use crate::battle::targeting::TargetingStrategy;
+use crate::drivers::LockResult;
+
+
+/**
+ * Everything a player sends to the match server, as single lines of json.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Join { strategy: TargetingStrategy },
+    // a placement, along with the height of the stack it left behind
+    Placement { lock_result: LockResult, stack_height: usize },
+    ToppedOut,
+}
+
+/**
+ * Everything the match server sends to its players, as single lines of json.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    // sent whenever a player joins, until the match is full
+    Lobby { joined: usize, num_players: usize },
+    Start,
+    // garbage for the player to raise, as the lines from each attack
+    Garbage { lines: Vec<usize> },
+    // sent to every player still standing, every so often
+    Status {
+        standing: usize,
+        kos: usize,
+        badge_points: usize,
+        attackers: usize,
+        garbage_pending: usize,
+    },
+    // sent to a player once they are out of the match, with the place they finished in
+    KnockedOut { place: usize },
+    Won,
+}
@@ -0,0 +1,313 @@
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::battle::{GARBAGE_DELAY, MAX_GARBAGE_PER_PLACEMENT};
+use crate::battle::attack_table::AttackTable;
+use crate::battle::garbage_queue::GarbageQueue;
+use crate::battle::targeting::*;
+use crate::drivers::LockResult;
+use crate::drivers::utils::tetrimino_chooser::{generate_rng_seed, rng_from_seed, SeededRng};
+use crate::game_core::defaults::settings::FRAMES_PER_SECOND;
+use crate::netplay::connection::{Connection, Host};
+use super::*;
+use super::protocol::*;
+
+
+// players still standing are sent their status this often
+const STATUS_INTERVAL: usize = FRAMES_PER_SECOND / 4;
+
+struct ServerPlayer {
+    // dropped once the player is knocked out
+    connection: Option<Connection>,
+    strategy: TargetingStrategy,
+    attack_table: AttackTable,
+    garbage_queue: GarbageQueue,
+    // the players this player's last attack was sent to
+    targets: Vec<usize>,
+    stack_height: usize,
+    // the last player to attack this player, who is credited if they are knocked out
+    last_attacker: Option<usize>,
+    kos: usize,
+    badge_points: usize,
+    place: Option<usize>,
+}
+
+impl ServerPlayer {
+    fn new(connection: Connection) -> Self {
+        Self {
+            connection: Some(connection),
+            strategy: TargetingStrategy::Random,
+            attack_table: AttackTable::new(),
+            garbage_queue: GarbageQueue::new(GARBAGE_DELAY),
+            targets: Vec::new(),
+            stack_height: 0,
+            last_attacker: None,
+            kos: 0,
+            badge_points: 0,
+            place: None,
+        }
+    }
+
+    fn is_standing(&self) -> bool {
+        self.place.is_none()
+    }
+
+    /**
+     * Sends the message, returning whether the player is still connected.
+     */
+    fn send(&mut self, message: &ServerMessage) -> bool {
+        match self.connection.as_mut() {
+            Some(connection) => connection.send(message).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/**
+ * Runs a match between many players without any game of its own.  Players play on their own
+ * machines and report each placement, and the server decides where the garbage goes.
+ */
+pub struct MatchServer {
+    host: Host,
+    num_players: usize,
+    players: Vec<ServerPlayer>,
+    started: bool,
+    current_frame: usize,
+    rng: SeededRng,
+}
+
+impl MatchServer {
+    pub fn new(num_players: usize) -> io::Result<Self> {
+        Ok(Self {
+            host: Host::new(MATCH_PORT)?,
+            num_players: num_players.clamp(MIN_PLAYERS, MAX_PLAYERS),
+            players: Vec::new(),
+            started: false,
+            current_frame: 0,
+            rng: rng_from_seed(&generate_rng_seed()),
+        })
+    }
+
+    fn get_standing_players(&self) -> Vec<usize> {
+        (0..self.players.len()).filter(|&i| self.players[i].is_standing()).collect()
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.started && self.get_standing_players().len() <= 1
+    }
+
+    fn broadcast(&mut self, message: &ServerMessage) {
+        for player in self.players.iter_mut() {
+            player.send(message);
+        }
+    }
+
+    fn accept_players(&mut self) -> io::Result<()> {
+        while self.players.len() < self.num_players {
+            match self.host.accept()? {
+                Some(connection) => {
+                    self.players.push(ServerPlayer::new(connection));
+                    self.broadcast(&ServerMessage::Lobby { joined: self.players.len(), num_players: self.num_players });
+                },
+                None => break
+            }
+        }
+
+        if self.players.len() == self.num_players {
+            self.started = true;
+            self.broadcast(&ServerMessage::Start);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Frees up the slots of the players who left before the match started.
+     */
+    fn drop_disconnected_players(&mut self) {
+        let joined = self.players.len();
+        self.players.retain(|player| player.connection.is_some());
+        if self.players.len() < joined {
+            self.broadcast(&ServerMessage::Lobby { joined: self.players.len(), num_players: self.num_players });
+        }
+    }
+
+    /**
+     * What the given player's strategy knows about each of their opponents.
+     */
+    fn get_candidates(&self, attacker: usize) -> Vec<TargetCandidate> {
+        self.get_standing_players()
+            .into_iter()
+            .filter(|&i| i != attacker)
+            .map(|i| TargetCandidate {
+                player: i,
+                is_attacking: self.players[i].targets.contains(&attacker),
+                danger: self.players[i].stack_height + self.players[i].garbage_queue.get_pending_lines(),
+                badge_points: self.players[i].badge_points,
+            })
+            .collect()
+    }
+
+    fn place_piece(&mut self, player: usize, lock_result: LockResult, stack_height: usize) {
+        self.players[player].stack_height = stack_height;
+
+        let attack = self.players[player].attack_table.calculate_attack(&lock_result);
+        let attack = apply_badge_bonus(attack, self.players[player].badge_points);
+        let lines = self.players[player].garbage_queue.cancel(attack);
+        if lines > 0 {
+            let candidates = self.get_candidates(player);
+            let targets = choose_targets(self.players[player].strategy, &candidates, &mut self.rng);
+            for &target in targets.iter() {
+                self.players[target].garbage_queue.push(lines);
+                self.players[target].last_attacker = Some(player);
+            }
+            self.players[player].targets = targets;
+        }
+
+        // ready garbage rises once a piece is placed without clearing any lines
+        if lock_result.lines_cleared == 0 {
+            let lines = self.players[player].garbage_queue.take_ready(MAX_GARBAGE_PER_PLACEMENT);
+            if !lines.is_empty() && !self.players[player].send(&ServerMessage::Garbage { lines }) {
+                self.knock_out(player);
+            }
+        }
+    }
+
+    /**
+     * Takes the player out of the match, and gives their badges to whoever attacked them last.
+     */
+    fn knock_out(&mut self, player: usize) {
+        if !self.players[player].is_standing() {
+            return
+        }
+
+        let place = self.get_standing_players().len();
+        self.players[player].place = Some(place);
+        self.players[player].garbage_queue.clear();
+        self.players[player].send(&ServerMessage::KnockedOut { place });
+        self.players[player].connection = None;
+
+        if let Some(attacker) = self.players[player].last_attacker.filter(|&i| self.players[i].is_standing()) {
+            self.players[attacker].kos += 1;
+            self.players[attacker].badge_points += 1 + self.players[player].badge_points;
+        }
+
+        for other in self.players.iter_mut() {
+            other.targets.retain(|&target| target != player);
+        }
+    }
+
+    fn receive_messages(&mut self) {
+        for player in self.get_standing_players() {
+            while let Some(connection) = self.players[player].connection.as_mut() {
+                match connection.receive::<ClientMessage>() {
+                    Ok(Some(ClientMessage::Join { strategy })) => self.players[player].strategy = strategy,
+                    Ok(Some(ClientMessage::Placement { lock_result, stack_height })) if self.started => {
+                        self.place_piece(player, lock_result, stack_height);
+                    },
+                    Ok(Some(ClientMessage::Placement { .. })) => (),
+                    // a player who leaves before the match starts gives up their slot
+                    Ok(Some(ClientMessage::ToppedOut)) | Err(_) if !self.started => {
+                        self.players[player].connection = None;
+                        break;
+                    },
+                    Ok(Some(ClientMessage::ToppedOut)) | Err(_) => {
+                        self.knock_out(player);
+                        break;
+                    },
+                    Ok(None) => break,
+                }
+            }
+        }
+    }
+
+    fn send_status(&mut self) {
+        let standing = self.get_standing_players();
+        for &player in standing.iter() {
+            let status = ServerMessage::Status {
+                standing: standing.len(),
+                kos: self.players[player].kos,
+                badge_points: self.players[player].badge_points,
+                attackers: standing.iter().filter(|&&i| self.players[i].targets.contains(&player)).count(),
+                garbage_pending: self.players[player].garbage_queue.get_pending_lines(),
+            };
+
+            if !self.players[player].send(&status) {
+                self.knock_out(player);
+            }
+        }
+    }
+
+    /**
+     * Runs a single frame of the match.
+     */
+    pub fn next_frame(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.receive_messages();
+            self.drop_disconnected_players();
+            self.accept_players()?;
+            return Ok(())
+        }
+
+        // the match is full, so anyone who connects now is turned away
+        while self.host.accept()?.is_some() {}
+
+        self.current_frame += 1;
+        for player in self.players.iter_mut() {
+            player.garbage_queue.next_frame();
+        }
+
+        self.receive_messages();
+        if self.current_frame.is_multiple_of(STATUS_INTERVAL) {
+            self.send_status();
+        }
+
+        if self.is_decided() {
+            if let Some(winner) = self.get_standing_players().pop() {
+                self.players[winner].place = Some(1);
+                self.players[winner].send(&ServerMessage::Won);
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Each player's place, knockouts and badge points, best place first.
+     */
+    pub fn get_results(&self) -> Vec<String> {
+        let mut players = (0..self.players.len()).collect::<Vec<_>>();
+        players.sort_by_key(|&i| self.players[i].place);
+
+        players
+            .into_iter()
+            .map(|i| format!("#{}: player {}, {} KOs, {} badges, targeting {}",
+                self.players[i].place.unwrap_or(0), i + 1, self.players[i].kos,
+                get_badge_level(self.players[i].badge_points), self.players[i].strategy.get_name()))
+            .collect()
+    }
+}
+
+/**
+ * Hosts a single match for the given number of players, waiting for them all to connect.
+ */
+pub fn run_server(num_players: usize) -> io::Result<()> {
+    let mut server = MatchServer::new(num_players)?;
+    println!("waiting for {} players on port {}", server.num_players, MATCH_PORT);
+
+    let frame_duration = Duration::from_secs(1) / FRAMES_PER_SECOND as u32;
+    let mut next_frame_at = Instant::now();
+    while !server.is_decided() {
+        server.next_frame()?;
+
+        next_frame_at += frame_duration;
+        thread::sleep(next_frame_at.saturating_duration_since(Instant::now()));
+    }
+
+    for line in server.get_results() {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
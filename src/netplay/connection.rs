@@ -0,0 +1,136 @@
+use std::io::{self, prelude::*};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+
+pub const NETPLAY_PORT: u16 = 7878;
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/**
+ * A non-blocking connection to another game over the network, exchanging messages of any
+ * serializable type one line at a time.
+ */
+pub struct Connection {
+    stream: TcpStream,
+    // bytes received that haven't been returned as messages yet
+    buffer: Vec<u8>,
+    // bytes sent that the socket hasn't taken yet
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    /**
+     * Tries to connect to a host, without waiting longer than a fraction of a second.
+     */
+    pub fn connect(host: &str, port: u16) -> io::Result<Self> {
+        let address = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "could not resolve the host"))?;
+
+        Self::new(TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?)
+    }
+
+    /**
+     * Queues up the message, and sends as much of what is queued as the socket takes without
+     * blocking.  The rest is sent by later calls to `send`, `flush` or `receive`.
+     */
+    pub fn send(&mut self, message: &impl Serialize) -> io::Result<()> {
+        serde_json::to_writer(&mut self.outgoing, message)?;
+        self.outgoing.push(b'\n');
+
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * The number of bytes waiting to be sent, which keeps growing if the other side stops reading.
+     */
+    pub fn get_backlog(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    /**
+     * Returns the next whole message received, if there is one.  The other side closing the
+     * connection is reported as an error, once every message sent before it was received.
+     */
+    pub fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Option<T>> {
+        // a connection that can no longer be sent to may still have messages left to receive
+        let _ = self.flush();
+
+        let mut chunk = [0; 4096];
+        while !self.closed {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        match self.buffer.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+                Ok(Some(serde_json::from_slice(&line)?))
+            },
+            None if self.closed => Err(io::ErrorKind::UnexpectedEof.into()),
+            None => Ok(None),
+        }
+    }
+}
+
+/**
+ * Listens for a single player to connect, without blocking.
+ */
+pub struct Host {
+    listener: TcpListener,
+}
+
+impl Host {
+    pub fn new(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+        })
+    }
+
+    pub fn accept(&self) -> io::Result<Option<Connection>> {
+        match self.listener.accept() {
+            Ok((stream, _)) => Connection::new(stream).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
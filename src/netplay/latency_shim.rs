@@ -0,0 +1,87 @@
+use std::io::{self, prelude::*};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::connection::NETPLAY_PORT;
+
+
+// connecting to this port instead of the host's goes through the shim
+pub const SHIM_PORT: u16 = NETPLAY_PORT + 1;
+
+/**
+ * Passes everything read from one stream on to the other, holding each chunk back for the
+ * latency plus some random jitter.  Chunks are never reordered, the same as over TCP.
+ */
+fn forward_with_latency(mut from: TcpStream, mut to: TcpStream, latency: Duration, jitter: Duration) {
+    let (sender, receiver) = mpsc::channel::<(Instant, Vec<u8>)>();
+
+    thread::spawn(move || {
+        let mut chunk = [0; 4096];
+        let mut last_delivery = Instant::now();
+        loop {
+            let n = match from.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+
+            let delay = latency + jitter.mul_f64(rand::thread_rng().gen::<f64>());
+            last_delivery = last_delivery.max(Instant::now() + delay);
+            if sender.send((last_delivery, chunk[..n].to_vec())).is_err() {
+                break
+            }
+        }
+    });
+
+    thread::spawn(move || {
+        for (delivery, bytes) in receiver.iter() {
+            let now = Instant::now();
+            if delivery > now {
+                thread::sleep(delivery - now);
+            }
+
+            if to.write_all(&bytes).is_err() {
+                break
+            }
+        }
+
+        let _ = to.shutdown(Shutdown::Write);
+    });
+}
+
+/**
+ * Listens on the shim port, and forwards every connection to the host on the netplay port with
+ * artificial latency and jitter in both directions.  This is for testing how network games cope
+ * with a slow connection, by connecting through the shim on the same machine.  The shim keeps
+ * running for as long as the game does, so starting it again just reuses the running one.
+ */
+pub fn start_latency_shim(host: &str, latency: Duration, jitter: Duration) -> io::Result<()> {
+    let listener = match TcpListener::bind(("127.0.0.1", SHIM_PORT)) {
+        Ok(listener) => listener,
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let host = host.to_string();
+
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            // a client that can't reach the host is simply disconnected, and may try again
+            let (client, server) = match (client, TcpStream::connect((host.as_str(), NETPLAY_PORT))) {
+                (Ok(client), Ok(server)) => (client, server),
+                _ => continue,
+            };
+
+            let _ = client.set_nodelay(true);
+            let _ = server.set_nodelay(true);
+            if let (Ok(client_clone), Ok(server_clone)) = (client.try_clone(), server.try_clone()) {
+                forward_with_latency(client, server_clone, latency, jitter);
+                forward_with_latency(server, client_clone, latency, jitter);
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use super::connection::NETPLAY_PORT;
+
+
+pub const LOBBY_PORT: u16 = NETPLAY_PORT + 4;
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+// games that haven't been announced for this long are dropped from the lobby
+const GAME_TIMEOUT: Duration = Duration::from_secs(3);
+
+/**
+ * What a host broadcasts about their game to everyone on the network.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Announcement {
+    // tells apart the same game announced from different addresses, like loopback and the LAN
+    pub id: u64,
+    pub name: String,
+    pub mode: String,
+    pub players: usize,
+    pub max_players: usize,
+    // the port the game is hosted on
+    pub port: u16,
+}
+
+impl Announcement {
+    pub fn new(mode: &str, max_players: usize, port: u16) -> Self {
+        let name = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "tetrust".to_string());
+
+        Self {
+            id: rand::thread_rng().gen(),
+            name,
+            mode: mode.to_string(),
+            players: 1,
+            max_players,
+            port,
+        }
+    }
+}
+
+/**
+ * Announces a hosted game once a second, both to the LAN and to loopback, so that games on the
+ * same machine can be found as well.
+ */
+pub struct Advertiser {
+    // the game is just not advertised if there is no socket to broadcast from
+    socket: Option<UdpSocket>,
+    announcement: Announcement,
+    last_announced: Option<Instant>,
+}
+
+impl Advertiser {
+    pub fn new(announcement: Announcement) -> Self {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .and_then(|socket| socket.set_broadcast(true).map(|_| socket))
+            .ok();
+
+        Self {
+            socket,
+            announcement,
+            last_announced: None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        if self.last_announced.filter(|last| last.elapsed() < ANNOUNCE_INTERVAL).is_some() {
+            return
+        }
+        self.last_announced = Some(Instant::now());
+
+        if let (Some(socket), Ok(message)) = (self.socket.as_ref(), serde_json::to_vec(&self.announcement)) {
+            // either may fail without a network, which only means nobody there can see the game
+            let _ = socket.send_to(&message, (Ipv4Addr::BROADCAST, LOBBY_PORT));
+            let _ = socket.send_to(&message, (Ipv4Addr::LOCALHOST, LOBBY_PORT));
+        }
+    }
+}
+
+pub struct LobbyGame {
+    pub address: IpAddr,
+    pub announcement: Announcement,
+    last_seen: Instant,
+}
+
+/**
+ * Listens for the games being announced, keeping track of the ones still being hosted.
+ */
+pub struct LobbyBrowser {
+    socket: io::Result<UdpSocket>,
+    games: Vec<LobbyGame>,
+}
+
+impl LobbyBrowser {
+    pub fn new() -> Self {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, LOBBY_PORT))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+
+        Self {
+            socket,
+            games: Vec::new(),
+        }
+    }
+
+    pub fn get_error(&self) -> Option<&io::Error> {
+        self.socket.as_ref().err()
+    }
+
+    pub fn get_games(&self) -> &[LobbyGame] {
+        &self.games
+    }
+
+    pub fn update(&mut self) {
+        let mut buffer = [0; 1024];
+        while let Ok(socket) = self.socket.as_ref() {
+            let (length, sender) = match socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(_) => break,
+            };
+
+            let announcement = match serde_json::from_slice::<Announcement>(&buffer[..length]) {
+                Ok(announcement) => announcement,
+                Err(_) => continue,
+            };
+
+            match self.games.iter_mut().find(|game| game.announcement.id == announcement.id) {
+                Some(game) => {
+                    game.announcement = announcement;
+                    game.last_seen = Instant::now();
+                },
+                None => self.games.push(LobbyGame {
+                    address: sender.ip(),
+                    announcement,
+                    last_seen: Instant::now(),
+                }),
+            }
+        }
+
+        self.games.retain(|game| game.last_seen.elapsed() < GAME_TIMEOUT);
+    }
+}
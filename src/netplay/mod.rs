@@ -0,0 +1,42 @@
+pub mod connection;
+pub mod latency_shim;
+pub mod lobby;
+pub mod protocol;
+pub mod rollback;
+
+use crate::drivers::*;
+use crate::drivers::classic_driver::ClassicDriver;
+use crate::drivers::cascade_driver::CascadeDriver;
+use crate::drivers::sticky_driver::StickyDriver;
+
+
+/**
+ * A mode that can be played over the network.  Both sides build their drivers from the seed
+ * the host chose, so that they simulate the same game.
+ */
+#[derive(Clone)]
+pub struct NetplayMode {
+    pub name: &'static str,
+    pub get_driver: fn(Vec<u8>) -> Box<dyn Driver>,
+}
+
+pub fn get_netplay_modes() -> Vec<NetplayMode> {
+    vec![
+        NetplayMode {
+            name: "classic",
+            get_driver: |rng_seed| DriverBuilder::<ClassicDriver>::new().with_rng_seed(rng_seed).build_boxed(),
+        },
+        NetplayMode {
+            name: "cascade",
+            get_driver: |rng_seed| DriverBuilder::<CascadeDriver>::new().with_rng_seed(rng_seed).build_boxed(),
+        },
+        NetplayMode {
+            name: "sticky",
+            get_driver: |rng_seed| DriverBuilder::<StickyDriver>::new().with_rng_seed(rng_seed).build_boxed(),
+        },
+    ]
+}
+
+pub fn find_netplay_mode(name: &str) -> Option<NetplayMode> {
+    get_netplay_modes().into_iter().find(|mode| mode.name == name)
+}
@@ -0,0 +1,21 @@
+use serde::{Serialize, Deserialize};
+
+use crate::debugging::drivers::recording::Action;
+
+
+/**
+ * Everything sent between the two sides of a network game.  Each message is sent as a single
+ * line of json.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Message {
+    // sent by the host once a player connects, so that both sides build the same game
+    Hello { mode: String, rng_seed: Vec<u8> },
+    // the actions the sender's player performs on the given frame
+    Inputs { frame: usize, actions: Vec<Action> },
+    // lines of garbage the sender's player sent on the given frame
+    Garbage { frame: usize, lines: usize },
+    // a hash of the sender's simulation, once the given frame has been simulated
+    StateHash { frame: usize, hash: u64 },
+    Disconnect,
+}
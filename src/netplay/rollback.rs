@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::battle::Battle;
+use crate::debugging::drivers::recording::Action;
+use crate::drivers::*;
+use super::connection::Connection;
+use super::protocol::Message;
+
+
+// the simulation may run this many frames ahead of the other side's last known inputs
+pub const MAX_ROLLBACK_FRAMES: usize = 30;
+const HASH_INTERVAL: usize = 60;
+
+/**
+ * A game that can be simulated a frame at a time, and rolled back to an earlier frame.
+ */
+pub trait Simulation {
+    type Snapshot;
+
+    fn take_snapshot(&self) -> Self::Snapshot;
+    fn restore_snapshot(&mut self, snapshot: &Self::Snapshot);
+
+    // simulates the next frame, given each player's actions on it
+    fn simulate_frame(&mut self, actions: &[Vec<Action>]);
+
+    fn hash_state(&self) -> u64;
+    fn get_lines_sent(&self, player: usize) -> usize;
+}
+
+/**
+ * Hashes everything about a battle that both sides must agree on.
+ */
+pub fn hash_battle(battle: &Battle, drivers: &[&dyn Driver]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (i, driver) in drivers.iter().enumerate() {
+        let game_core = driver.get_game_core();
+        // only the active rows hold cells, and there are none at all on an empty board
+        game_core.get_board().get_cells().hash(&mut hasher);
+
+        let active_tetrimino = game_core.get_active_tetrimino();
+        for (j, point) in active_tetrimino.get_points().into_iter().enumerate() {
+            (point.x(), point.y(), active_tetrimino.get_point_value(j)).hash(&mut hasher);
+        }
+
+        driver.get_score().hash(&mut hasher);
+        driver.get_lines_cleared().hash(&mut hasher);
+        driver.is_game_over().hash(&mut hasher);
+        battle.get_garbage_pending(i).hash(&mut hasher);
+        battle.get_lines_sent(i).hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RollbackStatus {
+    Running,
+    Disconnected(String),
+    // the frame on which the two simulations were found to disagree
+    Desynced(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Check {
+    StateHash,
+    Garbage,
+}
+
+/**
+ * Values that both sides compute for the same frame, which must agree once both are known.
+ */
+struct Checks {
+    local: HashMap<(Check, usize), u64>,
+    remote: HashMap<(Check, usize), u64>,
+}
+
+impl Checks {
+    fn new() -> Self {
+        Self {
+            local: HashMap::new(),
+            remote: HashMap::new(),
+        }
+    }
+
+    /**
+     * Records a value, returning whether it agrees with the other side's, if that is known.
+     */
+    fn add(&mut self, check: Check, frame: usize, value: u64, is_local: bool) -> bool {
+        let (own, other) = if is_local {
+            (&mut self.local, &mut self.remote)
+        } else {
+            (&mut self.remote, &mut self.local)
+        };
+
+        match other.remove(&(check, frame)) {
+            Some(other_value) => other_value == value,
+            None => {
+                own.insert((check, frame), value);
+                true
+            }
+        }
+    }
+}
+
+/**
+ * A frame that was simulated, kept until the other side's inputs for it are known.
+ */
+struct FrameRecord<T> {
+    // the state from before the frame was simulated
+    snapshot: T,
+    local_actions: Vec<Action>,
+    // the actions the other side was predicted to perform, until they are known
+    remote_actions: Vec<Action>,
+
+    // the results of the frame, compared against the other side's once the frame is confirmed
+    lines_sent: Vec<usize>,
+    hash: Option<u64>,
+}
+
+/**
+ * Keeps both sides of a network game simulating the same frames with the same inputs, without
+ * waiting on the network.  The other side is predicted to do nothing until its inputs arrive,
+ * and when they turn out to differ, the simulation is rolled back to the first frame that was
+ * predicted wrong and simulated back up to the present.
+ */
+pub struct Rollback<T: Simulation> {
+    connection: Connection,
+    local_player: usize,
+    num_players: usize,
+    status: RollbackStatus,
+
+    // the next frame to be simulated
+    frame: usize,
+    // every frame simulated since the first unchecked one
+    records: VecDeque<FrameRecord<T::Snapshot>>,
+    checked_frame: usize,
+    checked_lines_sent: Vec<usize>,
+
+    // the other side's inputs, which are known for every frame before the confirmed frame
+    remote_inputs: BTreeMap<usize, Vec<Action>>,
+    confirmed_frame: usize,
+    checks: Checks,
+}
+
+impl<T: Simulation> Rollback<T> {
+    pub fn new(connection: Connection, local_player: usize, num_players: usize) -> Self {
+        Self {
+            connection,
+            local_player,
+            num_players,
+            status: RollbackStatus::Running,
+
+            frame: 0,
+            records: VecDeque::new(),
+            checked_frame: 0,
+            checked_lines_sent: vec![0; num_players],
+
+            remote_inputs: BTreeMap::new(),
+            confirmed_frame: 0,
+            checks: Checks::new(),
+        }
+    }
+
+    pub fn get_status(&self) -> &RollbackStatus {
+        &self.status
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == RollbackStatus::Running
+    }
+
+    pub fn get_local_player(&self) -> usize {
+        self.local_player
+    }
+
+    /**
+     * Once the simulation is too far ahead of the other side, it waits for them to catch up.
+     */
+    pub fn is_stalled(&self) -> bool {
+        self.frame >= self.confirmed_frame + MAX_ROLLBACK_FRAMES
+    }
+
+    fn send(&mut self, message: &Message) {
+        if let Err(e) = self.connection.send(message) {
+            self.status = RollbackStatus::Disconnected(e.to_string());
+        }
+    }
+
+    fn check(&mut self, check: Check, frame: usize, value: u64, is_local: bool) {
+        if !self.checks.add(check, frame, value, is_local) && self.is_running() {
+            self.status = RollbackStatus::Desynced(frame);
+        }
+    }
+
+    /**
+     * Handles every message received from the other side.
+     */
+    fn poll(&mut self) {
+        loop {
+            let message = match self.connection.receive() {
+                Ok(Some(message)) => message,
+                Ok(None) => return,
+                Err(e) => {
+                    self.status = RollbackStatus::Disconnected(e.to_string());
+                    return
+                }
+            };
+
+            match message {
+                Message::Inputs { frame, actions } => { self.remote_inputs.insert(frame, actions); },
+                Message::Garbage { frame, lines } => self.check(Check::Garbage, frame, lines as u64, false),
+                Message::StateHash { frame, hash } => self.check(Check::StateHash, frame, hash, false),
+                Message::Disconnect => {
+                    self.status = RollbackStatus::Disconnected("the other player left".to_string());
+                    return
+                },
+                Message::Hello { .. } => (),
+            }
+        }
+    }
+
+    fn simulate_frame(&mut self, simulation: &mut T, local_actions: Vec<Action>) {
+        let remote_actions = self.remote_inputs.get(&self.frame).cloned().unwrap_or_default();
+        let snapshot = simulation.take_snapshot();
+
+        let mut actions = vec![Vec::new(); self.num_players];
+        actions[self.local_player] = local_actions.clone();
+        actions[1 - self.local_player] = remote_actions.clone();
+        simulation.simulate_frame(&actions);
+
+        self.records.push_back(FrameRecord {
+            snapshot,
+            local_actions,
+            remote_actions,
+            lines_sent: (0..self.num_players).map(|player| simulation.get_lines_sent(player)).collect(),
+            hash: if self.frame.is_multiple_of(HASH_INTERVAL) { Some(simulation.hash_state()) } else { None },
+        });
+        self.frame += 1;
+    }
+
+    /**
+     * Rolls back to the first frame whose prediction turned out to be wrong, and simulates
+     * every frame since then again.
+     */
+    fn correct_predictions(&mut self, simulation: &mut T) {
+        let first_record_frame = self.checked_frame;
+        let mispredicted_frame = (self.confirmed_frame..self.frame)
+            .find(|frame| match self.remote_inputs.get(frame) {
+                Some(actions) => *actions != self.records[frame - first_record_frame].remote_actions,
+                None => false,
+            });
+
+        while self.remote_inputs.contains_key(&self.confirmed_frame) {
+            self.confirmed_frame += 1;
+        }
+
+        let mispredicted_frame = match mispredicted_frame {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        let records = self.records.split_off(mispredicted_frame - first_record_frame);
+        simulation.restore_snapshot(&records[0].snapshot);
+        self.frame = mispredicted_frame;
+        for record in records.into_iter() {
+            self.simulate_frame(simulation, record.local_actions);
+        }
+    }
+
+    /**
+     * Compares every newly confirmed frame against the other side's simulation of it.
+     */
+    fn check_confirmed_frames(&mut self) {
+        while self.checked_frame < self.confirmed_frame.min(self.frame) {
+            let record = self.records.pop_front().unwrap();
+            let frame = self.checked_frame;
+            for player in 0..self.num_players {
+                let lines = record.lines_sent[player] - self.checked_lines_sent[player];
+                if lines == 0 {
+                    continue
+                }
+
+                if player == self.local_player {
+                    self.send(&Message::Garbage { frame, lines });
+                } else {
+                    self.check(Check::Garbage, frame, lines as u64, true);
+                }
+            }
+
+            if let Some(hash) = record.hash {
+                self.send(&Message::StateHash { frame, hash });
+                self.check(Check::StateHash, frame, hash, true);
+            }
+
+            self.checked_lines_sent = record.lines_sent;
+            self.remote_inputs.remove(&frame);
+            self.checked_frame += 1;
+        }
+    }
+
+    /**
+     * Brings the simulation up to date with the other side's inputs, then simulates the next
+     * frame with the local player's actions, unless the simulation has to wait.
+     */
+    pub fn update(&mut self, simulation: &mut T, local_actions: Vec<Action>) {
+        if !self.is_running() {
+            return
+        }
+
+        self.poll();
+        self.correct_predictions(simulation);
+        if !self.is_stalled() {
+            self.send(&Message::Inputs { frame: self.frame, actions: local_actions.clone() });
+            self.simulate_frame(simulation, local_actions);
+        }
+
+        self.check_confirmed_frames();
+    }
+
+    /**
+     * Lets the other side know that the game was left on purpose.
+     */
+    pub fn disconnect(&mut self) {
+        if self.is_running() {
+            self.send(&Message::Disconnect);
+            self.status = RollbackStatus::Disconnected("left the game".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+    use crate::game_core::utils::point::Point;
+    use crate::game_states::netplay_state::VersusSimulation;
+    use crate::netplay::connection::*;
+    use crate::netplay::find_netplay_mode;
+    use crate::netplay::latency_shim::*;
+
+    const TEST_FRAMES: usize = 300;
+
+    /**
+     * What each player does on a frame, which the other side can't predict.  Nobody acts
+     * during the last frames, so that both sides end up knowing every input.
+     */
+    fn scripted_actions(frame: usize, player: usize) -> Vec<Action> {
+        if frame >= TEST_FRAMES - 2 * MAX_ROLLBACK_FRAMES {
+            return Vec::new()
+        }
+
+        match (frame + 7 * player) % 24 {
+            0 => vec![Action::TranslateLeft],
+            6 => vec![Action::RotateClockwise],
+            12 => vec![Action::TranslateRight, Action::TranslateRight],
+            18 => vec![Action::Fastfall],
+            _ => Vec::new(),
+        }
+    }
+
+    /**
+     * Hosts on one port and connects to another, which only differ when going through the shim.
+     */
+    fn connect(host_port: u16, connect_port: u16) -> (Connection, Connection) {
+        let host = Host::new(host_port).unwrap();
+        let client = Connection::connect("127.0.0.1", connect_port).unwrap();
+        loop {
+            if let Some(server) = host.accept().unwrap() {
+                return (server, client)
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /**
+     * Plays both sides of a match over the connections, checking that they agree throughout.
+     */
+    fn play_match(connections: (Connection, Connection)) {
+        let mode = find_netplay_mode("classic").unwrap();
+        let mut sides = vec![connections.0, connections.1]
+            .into_iter()
+            .enumerate()
+            .map(|(player, connection)| {
+                let simulation = VersusSimulation::new(&mode, vec![1], Point(1280, 720));
+                (Rollback::new(connection, player, 2), simulation)
+            })
+            .collect::<Vec<_>>();
+
+        while sides.iter().any(|(rollback, _)| rollback.frame < TEST_FRAMES) {
+            for (rollback, simulation) in sides.iter_mut() {
+                if rollback.frame < TEST_FRAMES {
+                    let actions = scripted_actions(rollback.frame, rollback.local_player);
+                    rollback.update(simulation, actions);
+                }
+                assert_eq!(rollback.status, RollbackStatus::Running);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // both sides have simulated every frame with the same inputs
+        assert_eq!(sides[0].1.hash_state(), sides[1].1.hash_state());
+    }
+
+    #[test]
+    fn a_match_over_loopback_stays_in_sync() {
+        play_match(connect(NETPLAY_PORT + 10, NETPLAY_PORT + 10));
+    }
+
+    #[test]
+    fn mispredicted_frames_are_simulated_again_in_sync() {
+        // the other side's inputs always arrive late, so every one of them is mispredicted
+        start_latency_shim("127.0.0.1", Duration::from_millis(30), Duration::from_millis(20)).unwrap();
+        play_match(connect(NETPLAY_PORT, SHIM_PORT));
+    }
+}
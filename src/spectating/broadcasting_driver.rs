@@ -0,0 +1,288 @@
+use crate::debugging::drivers::recording::Action;
+use crate::drivers::*;
+use crate::game_core::utils::point::Point;
+use crate::netplay::connection::{Connection, Host};
+use super::*;
+
+
+// spectators with this many bytes still waiting to be sent to them have fallen too far behind
+const MAX_SPECTATOR_BACKLOG: usize = 1 << 20;
+
+/**
+ * Broadcasts the wrapped game to any spectators that connect.  Spectators are sent the state
+ * of the game when they join, and then the actions performed on each frame, which is all they
+ * need to play out the same game themselves.
+ */
+pub struct BroadcastingDriver<'a> {
+    wrapped: Box<dyn Driver + 'a>,
+    // nobody can spectate if the port was already taken
+    host: Option<Host>,
+    spectators: Vec<Connection>,
+
+    mode: String,
+    rng_seed: Vec<u8>,
+
+    current_frame: usize,
+    current_frame_actions: Vec<Action>,
+    // the frame the game ends on is sent as soon as it ends, since no frame comes after it
+    current_frame_sent: bool,
+}
+
+impl<'a> BroadcastingDriver<'a> {
+    pub fn new(wrapped: Box<dyn Driver + 'a>, mode: &str) -> Self {
+        let rng_seed = wrapped.get_driver_core().get_rng_seed().to_vec();
+
+        Self {
+            wrapped,
+            host: Host::new(SPECTATOR_PORT).ok(),
+            spectators: Vec::new(),
+
+            mode: mode.to_string(),
+            rng_seed,
+
+            current_frame: 0,
+            current_frame_actions: Vec::new(),
+            current_frame_sent: false,
+        }
+    }
+
+    fn push_action(&mut self, action: Action) {
+        self.current_frame_actions.push(action);
+    }
+
+    /**
+     * Sends the actions performed on the current frame, which are final once the frame is over.
+     */
+    fn send_current_frame(&mut self) {
+        if !self.current_frame_sent {
+            let frame_actions = std::mem::take(&mut self.current_frame_actions);
+            self.broadcast(&SpectatorMessage::Frame { frame: self.current_frame, actions: frame_actions });
+            self.current_frame_sent = true;
+        }
+    }
+
+    /**
+     * Once the game is over, no more frames are played, so the spectators would otherwise never
+     * be sent the frame that ended it.
+     */
+    fn send_current_frame_if_game_over(&mut self) {
+        if self.wrapped.is_game_over() {
+            self.send_current_frame();
+        }
+    }
+
+    /**
+     * Sends the message to every spectator, dropping any that have disconnected or stopped
+     * reading.  Nothing waits on a spectator, so a slow one can't hold up the game.
+     */
+    fn broadcast(&mut self, message: &SpectatorMessage) {
+        self.spectators.retain_mut(|spectator| {
+            spectator.send(message).is_ok() && spectator.get_backlog() <= MAX_SPECTATOR_BACKLOG
+        });
+    }
+
+    fn broadcast_transition(&mut self, transition: &BoardTransition) {
+        if !transition.is_inert() {
+            self.broadcast(&SpectatorMessage::Transition { transition: transition.clone() });
+        }
+    }
+
+    /**
+     * Sends any spectators that just connected a keyframe of the game as it is now.  This is only
+     * done between frames, when every transition from the last frame has been finished.
+     */
+    fn admit_spectators(&mut self) {
+        let mut joining = Vec::new();
+        if let Some(host) = self.host.as_ref() {
+            while let Ok(Some(spectator)) = host.accept() {
+                joining.push(spectator);
+            }
+        }
+
+        if joining.is_empty() {
+            return
+        }
+
+        let keyframe = SpectatorMessage::Keyframe {
+            mode: self.mode.clone(),
+            rng_seed: self.rng_seed.clone(),
+            frame: self.current_frame,
+            state: Box::new(self.wrapped.save_state()),
+        };
+
+        for mut spectator in joining.into_iter() {
+            if spectator.send(&keyframe).is_ok() {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+}
+
+impl<'a> Driver for BroadcastingDriver<'a> {
+    fn get_driver_core(&self) -> &DriverCore {
+        self.wrapped.get_driver_core()
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        self.wrapped.get_driver_core_mut()
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        self.wrapped.get_mode_state()
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        self.wrapped.set_mode_state(mode_state)
+    }
+
+    fn take_snapshot(&self) -> DriverSnapshot {
+        self.wrapped.take_snapshot()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        self.wrapped.restore_snapshot(snapshot)
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        let mut status = self.wrapped.get_mode_status();
+        if !self.spectators.is_empty() {
+            status.push(format!("spectators: {}", self.spectators.len()));
+        }
+
+        status
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        self.wrapped.get_target_points()
+    }
+
+    fn next_frame(&mut self) -> BoardTransition {
+        // the previous frame is over, so its actions are final
+        self.send_current_frame();
+        self.admit_spectators();
+
+        self.current_frame += 1;
+        self.current_frame_sent = false;
+
+        let transition = self.wrapped.next_frame();
+        self.broadcast_transition(&transition);
+        self.send_current_frame_if_game_over();
+        transition
+    }
+
+    fn translate_left(&mut self) {
+        self.push_action(Action::TranslateLeft);
+
+        self.wrapped.translate_left()
+    }
+
+    fn translate_right(&mut self) {
+        self.push_action(Action::TranslateRight);
+
+        self.wrapped.translate_right()
+    }
+
+    fn rotate_clockwise(&mut self) {
+        self.push_action(Action::RotateClockwise);
+
+        self.wrapped.rotate_clockwise()
+    }
+
+    fn rotate_counterclockwise(&mut self) {
+        self.push_action(Action::RotateCounterClockwise);
+
+        self.wrapped.rotate_counterclockwise()
+    }
+
+    fn hold(&mut self) {
+        self.push_action(Action::Hold);
+
+        self.wrapped.hold();
+        self.send_current_frame_if_game_over();
+    }
+
+    fn activate_zone(&mut self) {
+        self.push_action(Action::ActivateZone);
+
+        self.wrapped.activate_zone()
+    }
+
+    fn start_fastfalling(&mut self) {
+        self.push_action(Action::StartFastfalling);
+
+        self.wrapped.start_fastfalling()
+    }
+
+    fn stop_fastfalling(&mut self) {
+        self.push_action(Action::StopFastfalling);
+
+        self.wrapped.stop_fastfalling()
+    }
+
+    fn fall(&mut self) -> BoardTransition {
+        self.push_action(Action::Fall);
+
+        let transition = self.wrapped.fall();
+        self.broadcast_transition(&transition);
+        self.send_current_frame_if_game_over();
+        transition
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        self.push_action(Action::Fastfall);
+
+        let transition = self.wrapped.fastfall();
+        self.broadcast_transition(&transition);
+        self.send_current_frame_if_game_over();
+        transition
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
+        let transition = self.wrapped.finish_transition(transition);
+        self.broadcast_transition(&transition);
+        self.send_current_frame_if_game_over();
+        transition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use crate::drivers::classic_driver::ClassicDriver;
+
+    fn finish_transitions(driver: &mut dyn Driver, mut transition: BoardTransition) {
+        while !transition.is_inert() {
+            transition = driver.finish_transition(transition);
+        }
+    }
+
+    #[test]
+    fn spectators_are_sent_the_frame_the_game_ends_on() {
+        let mut driver = BroadcastingDriver::new(DriverBuilder::<ClassicDriver>::new().with_rng_seed(vec![1]).build_boxed(), "classic");
+        let mut spectator = Connection::connect("127.0.0.1", SPECTATOR_PORT).unwrap();
+
+        // the spectator is let in between frames, and every piece is hard dropped until the stack tops out
+        while !driver.is_game_over() {
+            let transition = driver.next_frame();
+            finish_transitions(&mut driver, transition);
+            if !driver.is_game_over() {
+                let transition = driver.fastfall();
+                finish_transitions(&mut driver, transition);
+            }
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut last_frame = None;
+        while last_frame != Some(driver.current_frame) && Instant::now() < deadline {
+            match spectator.receive() {
+                Ok(Some(SpectatorMessage::Frame { frame, .. })) => last_frame = Some(frame),
+                Ok(_) => thread::sleep(Duration::from_millis(1)),
+                Err(e) => panic!("{}", e),
+            }
+        }
+
+        assert_eq!(last_frame, Some(driver.current_frame));
+    }
+}
@@ -0,0 +1,30 @@
+pub mod broadcasting_driver;
+pub mod spectating_driver;
+pub mod spectator_menu;
+
+use serde::{Serialize, Deserialize};
+
+use crate::debugging::drivers::recording::Action;
+use crate::drivers::*;
+use crate::netplay::connection::NETPLAY_PORT;
+
+
+pub const SPECTATOR_PORT: u16 = NETPLAY_PORT + 2;
+
+/**
+ * Everything a broadcast game sends to its spectators, as single lines of json.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub enum SpectatorMessage {
+    // everything needed to build the game as it was once the given frame was over
+    Keyframe {
+        mode: String,
+        rng_seed: Vec<u8>,
+        frame: usize,
+        state: Box<DriverState>,
+    },
+    // the actions performed on the given frame, sent once the frame is over
+    Frame { frame: usize, actions: Vec<Action> },
+    // each transition the game goes through, for the spectator to check its own copy of the game against
+    Transition { transition: BoardTransition },
+}
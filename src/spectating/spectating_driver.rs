@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use crate::debugging::drivers::recording::Action;
+use crate::drivers::*;
+use crate::game_core::utils::point::Point;
+use crate::netplay::connection::Connection;
+use super::*;
+
+
+/**
+ * Plays out a broadcast game as its frames arrive, similar to the way a replay is played from
+ * a recording.  Every transition is checked against the broadcaster's, and the spectator's own
+ * controls are ignored.
+ */
+pub struct SpectatingDriver<'a> {
+    wrapped: Box<dyn Driver + 'a>,
+    // the broadcast is gone once the game ends or the connection is lost
+    connection: Option<Connection>,
+
+    current_frame: usize,
+    frames: VecDeque<(usize, Vec<Action>)>,
+    expected_transitions: VecDeque<BoardTransition>,
+    produced_transitions: VecDeque<BoardTransition>,
+    in_sync: bool,
+}
+
+impl<'a> SpectatingDriver<'a> {
+    /**
+     * The wrapped driver must already have been loaded with the state of the given frame.
+     */
+    pub fn new(wrapped: Box<dyn Driver + 'a>, connection: Connection, current_frame: usize) -> Self {
+        Self {
+            wrapped,
+            connection: Some(connection),
+
+            current_frame,
+            frames: VecDeque::new(),
+            expected_transitions: VecDeque::new(),
+            produced_transitions: VecDeque::new(),
+            in_sync: true,
+        }
+    }
+
+    fn poll(&mut self) {
+        while let Some(connection) = self.connection.as_mut() {
+            match connection.receive() {
+                Ok(Some(SpectatorMessage::Frame { frame, actions })) => self.frames.push_back((frame, actions)),
+                Ok(Some(SpectatorMessage::Transition { transition })) => {
+                    self.expected_transitions.push_back(transition);
+                    self.compare_transitions();
+                },
+                Ok(Some(SpectatorMessage::Keyframe { .. })) => (),
+                Ok(None) => break,
+                Err(_) => self.connection = None,
+            }
+        }
+    }
+
+    fn compare_transitions(&mut self) {
+        while !self.expected_transitions.is_empty() && !self.produced_transitions.is_empty() {
+            let expected = self.expected_transitions.pop_front().unwrap();
+            let produced = self.produced_transitions.pop_front().unwrap();
+            self.in_sync &= expected == produced;
+        }
+    }
+
+    fn verify_transition(&mut self, transition: &BoardTransition) {
+        if !transition.is_inert() {
+            self.produced_transitions.push_back(transition.clone());
+            self.compare_transitions();
+        }
+    }
+}
+
+impl<'a> Driver for SpectatingDriver<'a> {
+    fn get_driver_core(&self) -> &DriverCore {
+        self.wrapped.get_driver_core()
+    }
+
+    fn get_driver_core_mut(&mut self) -> &mut DriverCore {
+        self.wrapped.get_driver_core_mut()
+    }
+
+    fn get_mode_state(&self) -> ModeState {
+        self.wrapped.get_mode_state()
+    }
+
+    fn set_mode_state(&mut self, mode_state: &ModeState) {
+        self.wrapped.set_mode_state(mode_state)
+    }
+
+    fn take_snapshot(&self) -> DriverSnapshot {
+        self.wrapped.take_snapshot()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &DriverSnapshot) {
+        self.wrapped.restore_snapshot(snapshot)
+    }
+
+    fn get_mode_status(&self) -> Vec<String> {
+        let mut status = self.wrapped.get_mode_status();
+        if !self.in_sync {
+            status.push("out of sync".to_string());
+        } else if self.connection.is_none() && self.frames.is_empty() {
+            status.push("broadcast over".to_string());
+        } else if self.frames.is_empty() {
+            status.push("waiting for the broadcast".to_string());
+        } else {
+            status.push("spectating".to_string());
+        }
+
+        status
+    }
+
+    fn get_target_points(&self) -> Vec<Point> {
+        self.wrapped.get_target_points()
+    }
+
+    /**
+     * The game only moves on once the broadcaster has finished the frame, so it waits in place
+     * whenever the broadcast falls behind.
+     */
+    fn next_frame(&mut self) -> BoardTransition {
+        self.poll();
+        let actions = match self.frames.front() {
+            Some((frame, _)) if *frame == self.current_frame + 1 => self.frames.pop_front().unwrap().1,
+            _ => return BoardTransition::new(),
+        };
+
+        self.current_frame += 1;
+        let mut transitions = self.wrapped.next_frame();
+        self.verify_transition(&transitions);
+
+        for action in actions.into_iter() {
+            let transition = action.apply(self.wrapped.as_mut());
+            self.verify_transition(&transition);
+            transitions.add_from_transition(transition);
+        }
+
+        transitions
+    }
+
+    fn translate_left(&mut self) {}
+    fn translate_right(&mut self) {}
+    fn rotate_clockwise(&mut self) {}
+    fn rotate_counterclockwise(&mut self) {}
+    fn hold(&mut self) {}
+    fn activate_zone(&mut self) {}
+    fn start_fastfalling(&mut self) {}
+    fn stop_fastfalling(&mut self) {}
+
+    fn fall(&mut self) -> BoardTransition {
+        BoardTransition::new()
+    }
+
+    fn fastfall(&mut self) -> BoardTransition {
+        BoardTransition::new()
+    }
+
+    fn finish_transition(&mut self, transition: BoardTransition) -> BoardTransition {
+        let transition = self.wrapped.finish_transition(transition);
+        self.verify_transition(&transition);
+        transition
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use async_trait::async_trait;
+
+use crate::GameMode;
+use crate::game_states::*;
+use crate::game_states::menu_state::*;
+use crate::game_states::tetris_state::TetrisState;
+use crate::netplay::connection::Connection;
+use super::*;
+use super::spectating_driver::SpectatingDriver;
+
+
+const BROADCAST_HOST: &str = "localhost";
+
+/**
+ * Keeps trying to connect to a broadcast game, then waits for a keyframe to start spectating from.
+ */
+pub struct SpectatorState<'a> {
+    gamemodes: &'a HashMap<&'static str, GameMode>,
+    connection: Option<Connection>,
+    frames_until_retry: usize,
+}
+
+#[async_trait(?Send)]
+impl<'a> GameState<'a> for SpectatorState<'a> {
+    async fn run(mut self: Box<Self>, gamestate_manager: &mut GameStateManager<'a>) {
+        loop {
+            clear_background(BLACK);
+
+            if is_key_pressed(KeyCode::Escape) {
+                return;
+            }
+
+            match self.connection.as_mut() {
+                None => {
+                    if self.frames_until_retry == 0 {
+                        self.connection = Connection::connect(BROADCAST_HOST, SPECTATOR_PORT).ok();
+                        self.frames_until_retry = 60;
+                    } else {
+                        self.frames_until_retry -= 1;
+                    }
+                },
+                Some(connection) => match connection.receive() {
+                    // a keyframe always comes before anything else
+                    Ok(Some(SpectatorMessage::Keyframe { mode, rng_seed, frame, state })) => {
+                        let gamemode = match self.gamemodes.get(mode.as_str()) {
+                            Some(gamemode) => gamemode,
+                            None => return,
+                        };
+
+                        let mut driver = (gamemode.get_driver)(rng_seed);
+                        driver.load_state(&state);
+
+                        let connection = self.connection.take().unwrap();
+                        let state = TetrisState::new(
+                            Box::new(SpectatingDriver::new(driver, connection, frame)),
+                            (gamemode.get_renderer)(gamestate_manager.get_render_manager_factory()).build().await
+                        );
+                        gamestate_manager.get_gamestate_stack().push(state.boxed());
+                        return;
+                    },
+                    Ok(_) => (),
+                    Err(_) => self.connection = None,
+                },
+            }
+
+            let lines = [
+                (format!("looking for a broadcast on {}:{}", BROADCAST_HOST, SPECTATOR_PORT), WHITE, 32.0, 0.0),
+                ("escape to cancel".to_string(), GRAY, 24.0, 40.0),
+            ];
+            for (text, color, font_size, y_offset) in lines.iter() {
+                let dim = measure_text(text, None, *font_size as u16, 1.0);
+                draw_text(text, (screen_width() - dim.width) / 2.0, screen_height() / 2.0 + y_offset, *font_size, *color);
+            }
+
+            next_frame().await;
+        }
+    }
+}
+
+pub async fn get_spectator_state<'a>(gamemodes: &'a HashMap<&'static str, GameMode>) -> Box<dyn GameState<'a> + 'a> {
+    SpectatorState {
+        gamemodes,
+        connection: None,
+        frames_until_retry: 0,
+    }.boxed()
+}
+
+/**
+ * Lists every mode, each of which starts a game that spectators can watch.
+ */
+pub async fn get_broadcast_menu<'a>(gamemodes: &'a HashMap<&'static str, GameMode>) -> Box<dyn GameState<'a> + 'a> {
+    let mut names = gamemodes.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+
+    let menu_options = names
+        .into_iter()
+        .map(|name| MenuOption::new(
+            name.to_string(),
+            move |f| Box::pin(gamemodes[name].construct_gamestate(f, true))
+        ))
+        .collect::<Vec<_>>();
+
+    Box::new(MenuState::new(menu_options).await)
+}
@@ -0,0 +1,53 @@
+use macroquad::prelude::*;
+
+use super::widget::*;
+use crate::game_core::utils::point::Point;
+use crate::ui::rendering::*;
+
+
+/**
+ * A bar beside the board that fills up with the garbage waiting to rise beneath the stack.
+ */
+pub struct GarbageMeter {
+    area: (Point, Point),
+    // the garbage is tracked by the battle rather than the driver, so it is given to the meter
+    garbage_pending: usize,
+}
+
+impl GarbageMeter {
+    pub fn new(area: (Point, Point)) -> Self {
+        Self {
+            area,
+            garbage_pending: 0,
+        }
+    }
+
+    pub fn set_garbage_pending(&mut self, garbage_pending: usize) {
+        self.garbage_pending = garbage_pending;
+    }
+}
+
+impl Widget for GarbageMeter {
+    fn draw<'a>(&mut self, state: WidgetState, _: Renderer) {
+        let dimensions = self.area.1 - self.area.0;
+        draw_rectangle(
+            self.area.0.x() as f32,
+            self.area.0.y() as f32,
+            dimensions.x() as f32,
+            dimensions.y() as f32,
+            DARKGRAY,
+        );
+
+        // the meter is full once the pending garbage would fill the whole board
+        let board_height = state.driver.get_game_core().get_board().get_height();
+        let fullness = (self.garbage_pending as f32 / board_height as f32).min(1.0);
+        let height = dimensions.y() as f32 * fullness;
+        draw_rectangle(
+            self.area.0.x() as f32,
+            self.area.1.y() as f32 - height,
+            dimensions.x() as f32,
+            height,
+            RED,
+        );
+    }
+}
@@ -1,4 +1,6 @@
 pub mod tetrimino_display;
 pub mod tetris_board;
 pub mod widget;
-pub mod label;
\ No newline at end of file
+pub mod label;
+pub mod status_display;
+pub mod garbage_meter;
\ No newline at end of file
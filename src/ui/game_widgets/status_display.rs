@@ -0,0 +1,34 @@
+use macroquad::prelude::*;
+
+use super::widget::*;
+use crate::game_core::utils::point::Point;
+use crate::ui::rendering::*;
+
+
+/**
+ * Displays each of the mode specific status lines of the driver, one beneath the other.
+ */
+pub struct StatusDisplay {
+    location: Point,
+    color: Color,
+    font_size: f32,
+}
+
+impl StatusDisplay {
+    pub fn new(location: Point, color: Color, font_size: f32) -> Self {
+        Self {
+            location,
+            color,
+            font_size,
+        }
+    }
+}
+
+impl Widget for StatusDisplay {
+    fn draw<'a>(&mut self, state: WidgetState, _: Renderer) {
+        for (i, line) in state.driver.get_mode_status().iter().enumerate() {
+            let y = self.location.y() as f32 + i as f32 * self.font_size;
+            draw_text(line, self.location.x() as f32, y, self.font_size, self.color);
+        }
+    }
+}
\ No newline at end of file
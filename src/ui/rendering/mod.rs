@@ -4,7 +4,9 @@ use std::rc::Rc;
 use std::collections::HashMap;
 
 use crate::game_core::GameCore;
-use crate::drivers::BoardTransition;
+use crate::game_core::board::GARBAGE_VALUE;
+use crate::game_core::cell_value::{Item, get_color};
+use crate::drivers::{BoardTransition, StackVisibility};
 use crate::ui::assets::tilemap::TileMap;
 use crate::game_core::utils::point::*;
 use crate::ui::game_widgets::widget::WidgetState;
@@ -13,9 +15,14 @@ use crate::ui::utils::board_transition_progress::BoardTransitionsProgress;
 
 pub struct Renderer<'a> {
     game_core: &'a GameCore,
-    active_tetrimino_points: Vec<Point>,
+    // the cells and values of every active tetrimino, and the cells of every ghost
+    active_tiles: Vec<(Point, u32)>,
+    ghost_points: Vec<Point>,
     transition: &'a BoardTransition,
     transition_progress: BoardTransitionsProgress,
+    stack_visibility: StackVisibility,
+    stack_revealed: bool,
+    target_points: Vec<Point>,
 
     deleted_rows: i32,
     last_y: i32,
@@ -27,9 +34,26 @@ impl<'a> Renderer<'a> {
     fn new(game_core: &'a GameCore, transition: &'a BoardTransition, transition_progress: BoardTransitionsProgress, tile_map: Option<&'a TileMap>) -> Self {
         Self {
             game_core,
-            active_tetrimino_points: game_core.get_active_tetrimino().get_points(),
+            active_tiles: game_core
+                .get_pieces()
+                .flat_map(|piece| {
+                    let active_tetrimino = piece.get_active_tetrimino();
+                    active_tetrimino
+                        .get_points()
+                        .into_iter()
+                        .enumerate()
+                        .map(move |(i, p)| (p, active_tetrimino.get_point_value(i)))
+                })
+                .collect(),
+            ghost_points: game_core
+                .get_pieces()
+                .flat_map(|piece| piece.get_ghost_tetrimino().iter().cloned())
+                .collect(),
             transition,
             transition_progress,
+            stack_visibility: StackVisibility::visible(),
+            stack_revealed: false,
+            target_points: Vec::new(),
 
             deleted_rows: 0,
             last_y: 0,
@@ -38,6 +62,33 @@ impl<'a> Renderer<'a> {
         }
     }
 
+    fn with_stack_visibility(mut self, stack_visibility: StackVisibility, stack_revealed: bool) -> Self {
+        self.stack_visibility = stack_visibility;
+        self.stack_revealed = stack_revealed;
+        self
+    }
+
+    fn with_target_points(mut self, target_points: Vec<Point>) -> Self {
+        self.target_points = target_points;
+        self
+    }
+
+    /**
+     * Calculates how visible a placed cell is, based on how long ago it was placed.
+     */
+    fn placed_cell_alpha(&self, point: Point) -> f32 {
+        match self.stack_visibility.fade_frames {
+            Some(_) if self.stack_revealed => 1.0,
+            Some(0) => 0.0,
+            Some(fade_frames) => {
+                let board = self.game_core.get_board();
+                let age = board.get_current_frame().saturating_sub(board.get_fill_frame(point));
+                1.0 - (age as f32 / fade_frames as f32).min(1.0)
+            },
+            None => 1.0
+        }
+    }
+
     /**
      * This will be called on each piece on the board starting from (0, 0) to
      * (width, height), going by row.
@@ -72,6 +123,7 @@ impl<'a> Renderer<'a> {
                 .game_core
                 .get_board()
                 .get_cell(point);
+            alpha = self.placed_cell_alpha(point);
             // if the point is filled, check if the point is being deleted in the current transition
             if self.transition
                 .get_rows_deleted()
@@ -79,29 +131,28 @@ impl<'a> Renderer<'a> {
                     .iter()
                     .find(|&&y| y == point.y())
                     .is_some()) {
-                alpha = 1.0 - self.transition_progress.rows_deleted_progress();
+                alpha *= 1.0 - self.transition_progress.rows_deleted_progress();
             } else if self.transition
                 .get_points_deleted()
                 .map_or(false, |points| points
                     .iter()
                     .find(|&&p| p == point)
                     .is_some()) {
-                alpha = 1.0 - self.transition_progress.points_deleted_progress();
+                alpha *= 1.0 - self.transition_progress.points_deleted_progress();
+            } else if self.transition
+                .get_rows_risen()
+                .is_some_and(|rows| point.y() < rows as i32) {
+                // rows that were just pushed in beneath the stack fade in
+                alpha *= self.transition_progress.rows_risen_progress();
             }
-        // check if the current active tetrimino is currently taking up the tile
-        } else if let Some((i, _)) = self.active_tetrimino_points
+        // check if an active tetrimino is currently taking up the tile
+        } else if let Some((_, value)) = self.active_tiles
             .iter()
-            .enumerate()
-            .find(|(_, p)| **p == point)
+            .find(|(p, _)| *p == point)
         {
-            active_tile_value = self.game_core
-                .get_active_tetrimino()
-                .get_tetrimino()
-                .values
-                .get(i)
-                .cloned();
-        // finally, check if the ghost of the active tetrimino is taking up the tile
-        } else if self.game_core.get_ghost_tetriminio()
+            active_tile_value = Some(*value);
+        // finally, check if the ghost of an active tetrimino is taking up the tile
+        } else if self.ghost_points
             .iter()
             .find(|p| **p == point)
             .is_some()
@@ -135,12 +186,31 @@ impl<'a> Renderer<'a> {
                 point_fall_offset += (point_fall as f32) * self.transition_progress.points_falling_progress();
             }
 
+            // everything above newly risen rows slides up from where it was before they were inserted
+            if let Some(rows) = self.transition.get_rows_risen() {
+                if point.y() >= rows as i32 {
+                    point_fall_offset += (rows as f32) * (1.0 - self.transition_progress.rows_risen_progress());
+                }
+            }
+
             // using the number of rows beneath the current row that are disappearing, calculate fall based on the elapsed frames of the animation
             point_fall_offset *= cell_size as f32;
             let point_fall_offset = Point::unit_y(point_fall_offset as i32);
             let pixel = pixel + point_fall_offset;
             self.render_tile(pixel, cell_size, value, alpha);
         }
+
+        // targets are outlined on top of whatever is in the cell
+        if self.target_points.contains(&point) {
+            draw_rectangle_lines(
+                pixel.x() as f32,
+                pixel.y() as f32,
+                cell_size as f32,
+                cell_size as f32,
+                boarder * 8.0,
+                GOLD,
+            );
+        }
     }
 
     /**
@@ -148,6 +218,26 @@ impl<'a> Renderer<'a> {
      * value.
      */
     pub fn render_tile(&mut self, pixel: Point, cell_size: i32, value: u32, alpha: f32) {
+        if value == GARBAGE_VALUE {
+            // garbage is drawn the same way regardless of the tile map
+            let boarder = cell_size as f32 / 80.0;
+            let mut color = DARKGRAY;
+            color.a = alpha;
+
+            draw_rectangle(
+                pixel.x() as f32 + boarder,
+                pixel.y() as f32 + boarder,
+                cell_size as f32 - 2.0 * boarder,
+                cell_size as f32 - 2.0 * boarder,
+                color,
+            );
+            return
+        }
+
+        // only the color part of the value decides how the cell looks, apart from the item glyph drawn over it
+        let item = Item::from_value(value);
+        let value = get_color(value);
+
         if let Some(tile_map) = self.tile_map {
             let rect = tile_map.tiles[value as usize % tile_map.tiles.len()];
             let dest_size = cell_size as f32;
@@ -194,6 +284,21 @@ impl<'a> Renderer<'a> {
                 color,
             );
         }
+
+        if let Some(item) = item {
+            let font_size = cell_size as f32;
+            let dimensions = measure_text(item.get_glyph(), None, font_size as u16, 1.0);
+            let mut color = BLACK;
+            color.a = alpha;
+
+            draw_text(
+                item.get_glyph(),
+                pixel.x() as f32 + (cell_size as f32 - dimensions.width) / 2.0,
+                pixel.y() as f32 + (cell_size as f32 + dimensions.height) / 2.0,
+                font_size,
+                color,
+            );
+        }
     }
 }
 
@@ -209,6 +314,8 @@ impl RenderManager {
             widget_state.transition, 
             widget_state.transition_progress,
             self.tile_map.as_ref().map(|x| x.as_ref()))
+            .with_stack_visibility(widget_state.driver.get_stack_visibility(), widget_state.driver.is_stack_revealed())
+            .with_target_points(widget_state.driver.get_target_points())
     }
 }
 
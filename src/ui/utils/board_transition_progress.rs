@@ -5,6 +5,7 @@ const POINTS_DELETED_DURATION: usize = 10;
 const ROWS_DELETED_DURATION: usize = 10;
 const POINTS_FALLING_DURATION: usize = 10;
 const POINTS_ADDED_DURATION: usize = 0;
+const ROWS_RISEN_DURATION: usize = 10;
 
 #[derive(Clone, Copy, Debug)]
 pub struct BoardTransitionsProgress {
@@ -12,6 +13,7 @@ pub struct BoardTransitionsProgress {
     rows_deleted_total: usize,
     points_falling_total: usize,
     points_added_total: usize,
+    rows_risen_total: usize,
     longest_total: usize,
     elapsed: usize
 }
@@ -23,6 +25,7 @@ impl BoardTransitionsProgress {
             rows_deleted_total: ROWS_DELETED_DURATION,
             points_falling_total: POINTS_FALLING_DURATION,
             points_added_total: POINTS_ADDED_DURATION,
+            rows_risen_total: ROWS_RISEN_DURATION,
             longest_total: 10,
             elapsed: 0
         }
@@ -33,7 +36,8 @@ impl BoardTransitionsProgress {
             self.points_deleted_total, 
             self.rows_deleted_total, 
             self.points_falling_total, 
-            self.points_added_total
+            self.points_added_total,
+            self.rows_risen_total
         ].into_iter().max().unwrap();
 
         self
@@ -56,6 +60,10 @@ impl BoardTransitionsProgress {
             self.rows_deleted_total = 0;
         }
 
+        if board_transition.get_rows_risen().is_none() {
+            self.rows_risen_total = 0;
+        }
+
         self.with_recalculated_longest()
     }
 
@@ -99,6 +107,10 @@ impl BoardTransitionsProgress {
         self.transition_progress(self.points_added_total)
     }
 
+    pub fn rows_risen_progress(&self) -> f32 {
+        self.transition_progress(self.rows_risen_total)
+    }
+
     pub fn next_frame(&mut self) {
         self.elapsed += 1
     }